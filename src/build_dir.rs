@@ -36,13 +36,35 @@ impl BuildDir {
             .canonicalize_utf8()
             .context("canonicalize source path")?;
         let temp_dir = copy_tree(source, &name_base, gitignore, console)?;
+        let build_dir = BuildDir::from_temp_dir(temp_dir, leak_temp_dir)?;
+        fix_manifest(&build_dir.path.join("Cargo.toml"), &source_abs)?;
+        fix_cargo_config(&build_dir.path, &source_abs)?;
+        Ok(build_dir)
+    }
+
+    /// Make another copy of this build dir, including its `target` directory, so that
+    /// what's already been built here doesn't need to be built again.
+    ///
+    /// The manifest and cargo config were already fixed up when this directory was made,
+    /// so they're copied as they are.
+    pub fn copy_built(&self, leak_temp_dir: bool, console: &Console) -> Result<BuildDir> {
+        let name_base = format!(
+            "{}-",
+            self.path
+                .file_name()
+                .and_then(|name| name.strip_suffix(".tmp"))
+                .unwrap_or("cargo-mutants-unnamed")
+        );
+        let temp_dir = copy_tree(&self.path, &name_base, false, console)?;
+        BuildDir::from_temp_dir(temp_dir, leak_temp_dir)
+    }
+
+    fn from_temp_dir(temp_dir: TempDir, leak_temp_dir: bool) -> Result<BuildDir> {
         let path: Utf8PathBuf = temp_dir
             .path()
             .to_owned()
             .try_into()
             .context("tempdir path to UTF-8")?;
-        fix_manifest(&path.join("Cargo.toml"), &source_abs)?;
-        fix_cargo_config(&path, &source_abs)?;
         let temp_dir = if leak_temp_dir {
             let _ = temp_dir.into_path();
             info!(?path, "Build directory will be leaked for inspection");
@@ -50,8 +72,7 @@ impl BuildDir {
         } else {
             Some(temp_dir)
         };
-        let build_dir = BuildDir { temp_dir, path };
-        Ok(build_dir)
+        Ok(BuildDir { temp_dir, path })
     }
 
     /// Make a build dir that works in-place on the source directory.
@@ -86,6 +107,32 @@ mod test {
         assert!(build_dir.path().join("src").is_dir());
     }
 
+    #[test]
+    fn build_dir_copy_built() {
+        let workspace = Workspace::open("testdata/factorial").unwrap();
+        let build_dir = BuildDir::copy_from(&workspace.dir, true, false, &Console::new()).unwrap();
+        let target = build_dir.path().join("target");
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("built"), "").unwrap();
+        let copy = build_dir.copy_built(false, &Console::new()).unwrap();
+        assert_ne!(copy.path(), build_dir.path());
+        assert!(copy.path().join("target/built").is_file());
+        assert_eq!(
+            std::fs::read_to_string(copy.path().join("Cargo.toml")).unwrap(),
+            std::fs::read_to_string(build_dir.path().join("Cargo.toml")).unwrap()
+        );
+        assert_eq!(
+            std::fs::metadata(copy.path().join("src/bin/factorial.rs"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            std::fs::metadata(build_dir.path().join("src/bin/factorial.rs"))
+                .unwrap()
+                .modified()
+                .unwrap()
+        );
+    }
+
     #[test]
     fn build_dir_in_place() -> Result<()> {
         let workspace = Workspace::open("testdata/factorial")?;
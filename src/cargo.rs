@@ -14,19 +14,31 @@ use crate::process::{Process, ProcessStatus};
 use crate::*;
 
 /// Run cargo build, check, or test.
+///
+/// `extra_env` is set in the environment of cargo, in addition to the variables that
+/// are always set.
+///
+/// If `test_names` is given, only the tests with exactly those names are run in the
+/// test phase.
+#[allow(clippy::too_many_arguments)]
 pub fn run_cargo(
     build_dir: &BuildDir,
     packages: Option<&[&Package]>,
     phase: Phase,
     timeout: Duration,
     log_file: &mut LogFile,
+    extra_env: &[(String, String)],
+    test_names: Option<&[String]>,
     options: &Options,
     console: &Console,
 ) -> Result<PhaseResult> {
     let _span = debug_span!("run", ?phase).entered();
     let start = Instant::now();
-    let argv = cargo_argv(build_dir.path(), packages, phase, options);
-    let env = vec![
+    let mut argv = cargo_argv(build_dir.path(), packages, phase, options);
+    if let (Phase::Test, Some(test_names)) = (phase, test_names) {
+        add_test_filter(&mut argv, test_names, options);
+    }
+    let mut env = vec![
         ("CARGO_ENCODED_RUSTFLAGS".to_owned(), rustflags()),
         // The tests might use Insta <https://insta.rs>, and we don't want it to write
         // updates to the source tree, and we *certainly* don't want it to write
@@ -34,6 +46,7 @@ pub fn run_cargo(
         ("INSTA_UPDATE".to_owned(), "no".to_owned()),
         ("INSTA_FORCE_PASS".to_owned(), "0".to_owned()),
     ];
+    env.extend_from_slice(extra_env);
     let process_status = Process::run(&argv, &env, build_dir.path(), timeout, log_file, console)?;
     check_interrupted()?;
     debug!(?process_status, elapsed = ?start.elapsed());
@@ -63,7 +76,7 @@ pub fn cargo_bin() -> String {
 /// Make up the argv for a cargo check/build/test invocation, including argv[0] as the
 /// cargo binary itself.
 // (This is split out so it's easier to test.)
-fn cargo_argv(
+pub fn cargo_argv(
     build_dir: &Utf8Path,
     packages: Option<&[&Package]>,
     phase: Phase,
@@ -100,6 +113,21 @@ fn cargo_argv(
             cargo_args.push("--tests".to_string());
         }
     }
+    cargo_args.extend(package_args(build_dir, packages, options));
+    if phase == Phase::Test {
+        cargo_args.extend(options.additional_cargo_test_args.iter().cloned());
+    }
+    cargo_args
+}
+
+/// Make up the arguments selecting packages and features, and any additional cargo
+/// arguments, common to all cargo invocations.
+pub fn package_args(
+    build_dir: &Utf8Path,
+    packages: Option<&[&Package]>,
+    options: &Options,
+) -> Vec<String> {
+    let mut cargo_args = Vec::new();
     if let Some([package]) = packages {
         // Use the unambiguous form for this case; it works better when the same
         // package occurs multiple times in the tree with different versions?
@@ -127,19 +155,40 @@ fn cargo_argv(
             .map(|f| format!("--features={}", f)),
     );
     cargo_args.extend(options.additional_cargo_args.iter().cloned());
-    if phase == Phase::Test {
-        cargo_args.extend(options.additional_cargo_test_args.iter().cloned());
-    }
     cargo_args
 }
 
+/// Add arguments to a test argv so that it runs only the tests with exactly these names.
+pub fn add_test_filter(argv: &mut Vec<String>, test_names: &[String], options: &Options) {
+    match options.test_tool {
+        TestTool::Cargo => {
+            // Arguments after `--` are passed to the test binaries, and there might
+            // already be some from `--` in the additional test args.
+            if !argv.iter().any(|a| a == "--") {
+                argv.push("--".to_owned());
+            }
+            argv.push("--exact".to_owned());
+            argv.extend(test_names.iter().cloned());
+        }
+        TestTool::Nextest => {
+            // The filter expression is an option to nextest, so must come before any `--`.
+            let at = argv.iter().position(|a| a == "--").unwrap_or(argv.len());
+            let expression = test_names
+                .iter()
+                .map(|name| format!("test(={name})"))
+                .join(" | ");
+            argv.splice(at..at, ["-E".to_owned(), expression]);
+        }
+    }
+}
+
 /// Return adjusted CARGO_ENCODED_RUSTFLAGS, including any changes to cap-lints.
 ///
 /// This does not currently read config files; it's too complicated.
 ///
 /// See <https://doc.rust-lang.org/cargo/reference/environment-variables.html>
 /// <https://doc.rust-lang.org/rustc/lints/levels.html#capping-lints>
-fn rustflags() -> String {
+pub fn rustflags() -> String {
     let mut rustflags: Vec<String> = if let Some(rustflags) = env::var_os("CARGO_ENCODED_RUSTFLAGS")
     {
         rustflags
@@ -206,6 +255,7 @@ mod test {
         let package = Arc::new(Package {
             name: package_name.to_owned(),
             relative_manifest_path: relative_manifest_path.clone(),
+            path_dependency_dirs: Vec::new(),
         });
         let build_manifest_path = build_dir.join(relative_manifest_path);
         assert_eq!(
@@ -310,6 +360,80 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_filter_for_cargo() {
+        let mut options = Options::default();
+        let build_dir = Utf8Path::new("/tmp/buildXYZ");
+        let test_names = ["a::test_a".to_owned(), "tests::b".to_owned()];
+        let mut argv = cargo_argv(build_dir, None, Phase::Test, &options);
+        add_test_filter(&mut argv, &test_names, &options);
+        assert_eq!(
+            argv[1..],
+            [
+                "test",
+                "--workspace",
+                "--",
+                "--exact",
+                "a::test_a",
+                "tests::b"
+            ]
+        );
+
+        options.additional_cargo_test_args = vec!["--".to_owned(), "--test-threads=1".to_owned()];
+        let mut argv = cargo_argv(build_dir, None, Phase::Test, &options);
+        add_test_filter(&mut argv, &test_names, &options);
+        assert_eq!(
+            argv[1..],
+            [
+                "test",
+                "--workspace",
+                "--",
+                "--test-threads=1",
+                "--exact",
+                "a::test_a",
+                "tests::b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_for_nextest() {
+        let mut options = Options {
+            test_tool: TestTool::Nextest,
+            ..Default::default()
+        };
+        let build_dir = Utf8Path::new("/tmp/buildXYZ");
+        let test_names = ["a::test_a".to_owned(), "tests::b".to_owned()];
+        let mut argv = cargo_argv(build_dir, None, Phase::Test, &options);
+        add_test_filter(&mut argv, &test_names, &options);
+        assert_eq!(
+            argv[1..],
+            [
+                "nextest",
+                "run",
+                "--workspace",
+                "-E",
+                "test(=a::test_a) | test(=tests::b)"
+            ]
+        );
+
+        options.additional_cargo_test_args = vec!["--".to_owned(), "--nocapture".to_owned()];
+        let mut argv = cargo_argv(build_dir, None, Phase::Test, &options);
+        add_test_filter(&mut argv, &test_names[..1], &options);
+        assert_eq!(
+            argv[1..],
+            [
+                "nextest",
+                "run",
+                "--workspace",
+                "-E",
+                "test(=a::test_a)",
+                "--",
+                "--nocapture"
+            ]
+        );
+    }
+
     rusty_fork_test! {
         #[test]
         fn rustflags_with_no_environment_variables() {
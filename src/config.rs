@@ -8,6 +8,7 @@
 //!
 //! The config file is then merged in to the [Options].
 
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::fs::read_to_string;
 use std::path::Path;
@@ -17,6 +18,7 @@ use anyhow::Context;
 use camino::Utf8Path;
 use serde::Deserialize;
 
+use crate::mutate::Genre;
 use crate::options::TestTool;
 use crate::Result;
 
@@ -31,6 +33,12 @@ pub struct Config {
     pub error_values: Vec<String>,
     /// Generate mutants from source files matching these globs.
     pub examine_globs: Vec<String>,
+    /// Generate mutants of these genres, in addition to the default genres.
+    pub genres: Vec<Genre>,
+    /// Pairs of method names to swap for each other, in addition to the built-in pairs.
+    pub method_swaps: Vec<(String, String)>,
+    /// Replacement expressions for functions returning types that match these patterns.
+    pub replacements: BTreeMap<String, Vec<String>>,
     /// Exclude mutants from source files matching these globs.
     pub exclude_globs: Vec<String>,
     /// Exclude mutants from source files matches these regexps.
@@ -6,6 +6,7 @@ use std::fs::FileType;
 
 use anyhow::Context;
 use camino::{Utf8Path, Utf8PathBuf};
+use filetime::{set_file_mtime, FileTime};
 use ignore::WalkBuilder;
 use path_slash::PathExt;
 use tempfile::TempDir;
@@ -33,6 +34,9 @@ static SOURCE_EXCLUDE: &[&str] = &[
 /// files.
 ///
 /// Regardless, anything matching [SOURCE_EXCLUDE] is excluded.
+///
+/// The modification times of files are kept, so that cargo can reuse anything already
+/// built in the tree.
 pub fn copy_tree(
     from_path: &Utf8Path,
     name_base: &str,
@@ -78,6 +82,9 @@ pub fn copy_tree(
                     entry.path().to_slash_lossy(),
                 )
             })?;
+            let mtime = FileTime::from_last_modification_time(&entry.metadata()?);
+            set_file_mtime(&dest_path, mtime)
+                .with_context(|| format!("Failed to set mtime of {dest_path:?}"))?;
             total_bytes += bytes_copied;
             total_files += 1;
             console.copy_progress(total_bytes);
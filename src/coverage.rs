@@ -0,0 +1,516 @@
+// Copyright 2024 Martin Pool
+
+//! Measure which tests cover each function, so that only those tests are run against
+//! its mutants.
+//!
+//! With `--select-tests`, after the baseline passes, the tree is built again with
+//! `-C instrument-coverage` into a separate target directory, and each test is run on
+//! its own. `llvm-profdata` and `llvm-cov` then report which lines each test executed.
+//! A mutant is covered by a test if the test executed any line of the function
+//! containing it.
+//!
+//! Separately, with `--coverage-report`, mutants in lines that an existing LCOV report
+//! says were never executed are skipped.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use itertools::Itertools;
+use tracing::{debug, info, warn};
+
+use crate::build_dir::BuildDir;
+use crate::cargo::{add_test_filter, cargo_argv, cargo_bin, package_args, rustflags};
+use crate::console::Console;
+use crate::lcov::LineCoverage;
+use crate::log_file::LogFile;
+use crate::mutate::Mutant;
+use crate::options::{Options, TestTool};
+use crate::outcome::Phase;
+use crate::package::Package;
+use crate::process::Process;
+use crate::Result;
+
+/// The LLVM tools used to read coverage profiles.
+#[derive(Debug)]
+pub struct LlvmTools {
+    profdata: Utf8PathBuf,
+    cov: Utf8PathBuf,
+}
+
+impl LlvmTools {
+    /// Find `llvm-profdata` and `llvm-cov` for the toolchain used in this directory.
+    ///
+    /// This is checked before anything is built, so that a missing tool is reported
+    /// quickly.
+    pub fn find(dir: &Utf8Path) -> Result<LlvmTools> {
+        Ok(LlvmTools {
+            profdata: llvm_tool(dir, "llvm-profdata")?,
+            cov: llvm_tool(dir, "llvm-cov")?,
+        })
+    }
+}
+
+/// The tests that executed each line of the source tree.
+#[derive(Debug, Default)]
+pub struct TestCoverage {
+    /// The names of all the tests that were run.
+    tests: Vec<String>,
+    /// For each source file, relative to the tree, the lines executed by each test,
+    /// as an index into `tests`.
+    hits: HashMap<Utf8PathBuf, Vec<(usize, Vec<usize>)>>,
+    /// For each source file, relative to the tree, the lines that are instrumented,
+    /// whether or not they were executed.
+    instrumented: HashMap<Utf8PathBuf, BTreeSet<usize>>,
+    /// True if there are doctests that will be run by the test tool.
+    ///
+    /// Doctests aren't instrumented, and can't be selected by name, so it's not known
+    /// which code they execute.
+    has_doctests: bool,
+}
+
+impl TestCoverage {
+    /// Build the tests with coverage instrumentation, and run each of them to see which
+    /// lines it executes.
+    ///
+    /// Commands and their output are written to `log_file`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn measure(
+        build_dir: &BuildDir,
+        llvm_tools: &LlvmTools,
+        packages: &[&Package],
+        build_timeout: Duration,
+        test_timeout: Duration,
+        log_file: &mut LogFile,
+        options: &Options,
+        console: &Console,
+    ) -> Result<TestCoverage> {
+        let profile_temp_dir = tempfile::Builder::new()
+            .prefix("cargo-mutants-coverage-")
+            .tempdir()
+            .context("create temporary directory for coverage profiles")?;
+        let profile_dir = Utf8Path::from_path(profile_temp_dir.path())
+            .context("coverage profile directory is not UTF-8")?;
+        let instrumented_env = |profile_name: &str| {
+            vec![
+                (
+                    "CARGO_ENCODED_RUSTFLAGS".to_owned(),
+                    format!("-Cinstrument-coverage\x1f{}", rustflags()),
+                ),
+                (
+                    "CARGO_TARGET_DIR".to_owned(),
+                    build_dir
+                        .path()
+                        .join("target")
+                        .join("mutants-coverage")
+                        .to_string(),
+                ),
+                (
+                    "LLVM_PROFILE_FILE".to_owned(),
+                    profile_dir
+                        .join(format!("{profile_name}-%p-%m.profraw"))
+                        .to_string(),
+                ),
+                ("INSTA_UPDATE".to_owned(), "no".to_owned()),
+                ("INSTA_FORCE_PASS".to_owned(), "0".to_owned()),
+            ]
+        };
+        let run = |argv: &[String],
+                   env: &[(String, String)],
+                   timeout: Duration,
+                   log_file: &mut LogFile|
+         -> Result<String> {
+            let log_start = log_file.path().metadata()?.len();
+            let status = Process::run(argv, env, build_dir.path(), timeout, log_file, console)?;
+            if !status.is_success() {
+                bail!(
+                    "{} failed while measuring test coverage: {status:?}",
+                    argv[0]
+                );
+            }
+            let mut output = String::new();
+            let mut file = File::open(log_file.path())?;
+            file.seek(SeekFrom::Start(log_start))?;
+            file.read_to_string(&mut output)?;
+            Ok(output)
+        };
+
+        let mut build_argv = vec![
+            cargo_bin(),
+            "test".to_owned(),
+            "--no-run".to_owned(),
+            "--message-format=json".to_owned(),
+        ];
+        build_argv.extend(package_args(build_dir.path(), Some(packages), options));
+        let build_output = run(
+            &build_argv,
+            &instrumented_env("build"),
+            build_timeout,
+            log_file,
+        )?;
+        let objects = test_executables(&build_output);
+        debug!(?objects, "Built instrumented tests");
+        if objects.is_empty() {
+            bail!("No test executables were built while measuring test coverage");
+        }
+
+        let mut list_argv = vec![cargo_bin(), "test".to_owned()];
+        list_argv.extend(package_args(build_dir.path(), Some(packages), options));
+        list_argv.extend(options.additional_cargo_test_args.iter().cloned());
+        if !list_argv.iter().any(|a| a == "--") {
+            list_argv.push("--".to_owned());
+        }
+        list_argv.extend(["--list".to_owned(), "--format=terse".to_owned()]);
+        let list_output = run(
+            &list_argv,
+            &instrumented_env("list"),
+            test_timeout,
+            log_file,
+        )?;
+        let tests = test_names(&list_output);
+        // Nextest doesn't run doctests.
+        let has_doctests =
+            options.test_tool == TestTool::Cargo && listed_tests(&list_output).any(is_doctest);
+        debug!(has_doctests);
+        info!("Measuring coverage of {} tests", tests.len());
+
+        let mut hits: HashMap<Utf8PathBuf, Vec<(usize, Vec<usize>)>> = HashMap::new();
+        let mut instrumented: HashMap<Utf8PathBuf, BTreeSet<usize>> = HashMap::new();
+        for (i, test_name) in tests.iter().enumerate() {
+            let profile_name = format!("test{i}");
+            let mut test_argv = cargo_argv(build_dir.path(), Some(packages), Phase::Test, options);
+            add_test_filter(&mut test_argv, std::slice::from_ref(test_name), options);
+            run(
+                &test_argv,
+                &instrumented_env(&profile_name),
+                test_timeout,
+                log_file,
+            )?;
+            let raw_profiles = profile_dir
+                .read_dir_utf8()?
+                .map(|entry| entry.map(|e| e.path().to_owned()))
+                .filter_ok(|path| {
+                    path.file_name()
+                        .is_some_and(|n| n.starts_with(&format!("{profile_name}-")))
+                })
+                .collect::<std::io::Result<Vec<_>>>()?;
+            if raw_profiles.is_empty() {
+                warn!("No coverage profile was written for test {test_name}");
+                continue;
+            }
+            let profile_data = profile_dir.join(format!("{profile_name}.profdata"));
+            let mut merge_argv = vec![
+                llvm_tools.profdata.to_string(),
+                "merge".to_owned(),
+                "-sparse".to_owned(),
+            ];
+            merge_argv.extend(raw_profiles.iter().map(|p| p.to_string()));
+            merge_argv.extend(["-o".to_owned(), profile_data.to_string()]);
+            run(&merge_argv, &[], build_timeout, log_file)?;
+
+            // The report is long, so write it to a separate file rather than the log.
+            let mut report_file = LogFile::create_in(profile_dir, &profile_name)?;
+            let mut export_argv = vec![
+                llvm_tools.cov.to_string(),
+                "export".to_owned(),
+                "-format=lcov".to_owned(),
+                format!("-instr-profile={profile_data}"),
+                objects[0].to_string(),
+            ];
+            for object in &objects[1..] {
+                export_argv.extend(["-object".to_owned(), object.to_string()]);
+            }
+            let report = run(&export_argv, &[], build_timeout, &mut report_file)?;
+            let line_coverage = LineCoverage::parse(&report);
+            for path in line_coverage.paths() {
+                let Ok(relative_path) = path.strip_prefix(build_dir.path()) else {
+                    // Probably in a dependency.
+                    continue;
+                };
+                if let Some(lines) = line_coverage.lines(path) {
+                    instrumented
+                        .entry(relative_path.to_owned())
+                        .or_default()
+                        .extend(lines.keys());
+                }
+                let lines = line_coverage.hit_lines(path);
+                if !lines.is_empty() {
+                    hits.entry(relative_path.to_owned())
+                        .or_default()
+                        .push((i, lines));
+                }
+            }
+        }
+        Ok(TestCoverage {
+            tests,
+            hits,
+            instrumented,
+            has_doctests,
+        })
+    }
+
+    /// Return the number of tests whose coverage was measured.
+    pub fn test_count(&self) -> usize {
+        self.tests.len()
+    }
+
+    /// Return the names of the tests that execute the function containing a mutant.
+    ///
+    /// For mutants outside of any function, this is the tests that execute any line of
+    /// the mutant itself.
+    ///
+    /// Returns None if none of those lines are instrumented, such as for the value of a
+    /// constant, so it's not known which tests execute them. Similarly, if no tests
+    /// execute them but there are doctests, which might, this returns None.
+    pub fn covering_tests(&self, mutant: &Mutant) -> Option<Vec<String>> {
+        let path = &mutant.source_file.tree_relative_path;
+        let span = match &mutant.function {
+            Some(function) => &function.span,
+            None => &mutant.span,
+        };
+        let lines = span.start.line..=span.end.line;
+        let is_instrumented = self
+            .instrumented
+            .get(path)
+            .is_some_and(|instrumented| instrumented.range(lines.clone()).next().is_some());
+        if !is_instrumented {
+            return None;
+        }
+        let tests = self
+            .hits
+            .get(path)
+            .into_iter()
+            .flatten()
+            .filter(|(_, hit_lines)| hit_lines.iter().any(|line| lines.contains(line)))
+            .map(|(i, _)| self.tests[*i].clone())
+            .collect_vec();
+        if tests.is_empty() && self.has_doctests {
+            None
+        } else {
+            Some(tests)
+        }
+    }
+}
+
+/// Split mutants into those that might be covered by tests according to a coverage
+/// report, and those that are not covered.
+///
+/// A mutant is not covered if every instrumented line in its span was executed zero
+/// times. Mutants in files that aren't in the report, or whose spans contain no
+/// instrumented lines, are assumed to be covered.
+pub fn partition_uncovered(
+    mutants: Vec<Mutant>,
+    line_coverage: &LineCoverage,
+    tree_dir: &Utf8Path,
+) -> (Vec<Mutant>, Vec<Mutant>) {
+    let by_path = line_coverage.by_tree_path(tree_dir);
+    mutants.into_iter().partition(|mutant| {
+        let Some(lines) = by_path.get(&mutant.source_file.tree_relative_path) else {
+            return true;
+        };
+        let mut span_hits = lines
+            .range(mutant.span.start.line..=mutant.span.end.line)
+            .map(|(_, &hits)| hits)
+            .peekable();
+        span_hits.peek().is_none() || span_hits.any(|hits| hits > 0)
+    })
+}
+
+/// Find the test executables in the JSON messages from `cargo test --no-run`.
+fn test_executables(cargo_output: &str) -> Vec<Utf8PathBuf> {
+    cargo_output
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| {
+            message["reason"] == "compiler-artifact" && message["profile"]["test"] == true
+        })
+        .filter_map(|message| message["executable"].as_str().map(Utf8PathBuf::from))
+        .unique()
+        .collect()
+}
+
+/// Find the names of all the tests, including doctests, in the output of
+/// `cargo test -- --list --format=terse`.
+fn listed_tests(list_output: &str) -> impl Iterator<Item = &str> {
+    list_output
+        .lines()
+        .filter_map(|line| line.strip_suffix(": test"))
+}
+
+/// True if a test name is a doctest, like `src/lib.rs - factorial (line 3)`.
+fn is_doctest(name: &str) -> bool {
+    name.contains(" - ") && name.ends_with(')')
+}
+
+/// Find the test names from `cargo test -- --list --format=terse`.
+///
+/// Doctests aren't built with coverage instrumentation, so they're skipped.
+fn test_names(list_output: &str) -> Vec<String> {
+    listed_tests(list_output)
+        .filter(|name| !is_doctest(name))
+        .map(|name| name.to_owned())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+/// Find an LLVM tool from the `llvm-tools` rustup component of the toolchain used in
+/// this directory, or otherwise on the `PATH`.
+fn llvm_tool(dir: &Utf8Path, name: &str) -> Result<Utf8PathBuf> {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let tool_file_name = format!("{name}{}", std::env::consts::EXE_SUFFIX);
+    if let Ok(output) = Command::new(rustc)
+        .args(["--print", "sysroot"])
+        .current_dir(dir)
+        .output()
+    {
+        let sysroot = Utf8PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        if let Ok(targets) = sysroot.join("lib/rustlib").read_dir_utf8() {
+            for target in targets.flatten() {
+                let path = target.path().join("bin").join(&tool_file_name);
+                if path.is_file() {
+                    debug!(?path, "Found LLVM tool in sysroot");
+                    return Ok(path);
+                }
+            }
+        }
+    }
+    if Command::new(&tool_file_name)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+    {
+        return Ok(tool_file_name.into());
+    }
+    bail!("Can't find {name}, which is needed to measure test coverage: install it with `rustup component add llvm-tools-preview`");
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn partition_mutants_by_coverage_report() {
+        use crate::console::Console;
+        use crate::workspace::{PackageFilter, Workspace};
+
+        let workspace = Workspace::open("testdata/small_well_tested").unwrap();
+        let mutants = workspace
+            .mutants(&PackageFilter::All, &Options::default(), &Console::new())
+            .unwrap();
+        assert_eq!(mutants.len(), 4);
+        // The return value mutants are on line 5, and the operator mutants on line 7.
+        let line_coverage = LineCoverage::parse(indoc! { "
+            SF:/ci/small_well_tested/src/lib.rs
+            DA:4,2
+            DA:5,2
+            DA:6,10
+            DA:7,0
+            DA:8,10
+            end_of_record
+        " });
+        let (covered, uncovered) = partition_uncovered(mutants, &line_coverage, &workspace.dir);
+        assert_eq!(
+            covered
+                .iter()
+                .map(|m| m.span.start.line)
+                .collect::<Vec<_>>(),
+            [5, 5]
+        );
+        assert_eq!(
+            uncovered
+                .iter()
+                .map(|m| m.span.start.line)
+                .collect::<Vec<_>>(),
+            [7, 7]
+        );
+
+        // Files and lines that aren't in the report are assumed to be covered.
+        let mutants = workspace
+            .mutants(&PackageFilter::All, &Options::default(), &Console::new())
+            .unwrap();
+        let line_coverage = LineCoverage::parse("SF:src/other.rs\nDA:7,0\nend_of_record\n");
+        let (covered, uncovered) = partition_uncovered(mutants, &line_coverage, &workspace.dir);
+        assert_eq!((covered.len(), uncovered.len()), (4, 0));
+    }
+
+    #[test]
+    fn covering_tests_of_mutants() {
+        use crate::console::Console;
+        use crate::workspace::{PackageFilter, Workspace};
+
+        let workspace = Workspace::open("testdata/small_well_tested").unwrap();
+        let mutants = workspace
+            .mutants(&PackageFilter::All, &Options::default(), &Console::new())
+            .unwrap();
+        let path = Utf8PathBuf::from("src/lib.rs");
+        let mut test_coverage = TestCoverage {
+            tests: vec!["test::test_factorial".to_owned(), "test::other".to_owned()],
+            hits: HashMap::from([(path.clone(), vec![(0, vec![4, 5])])]),
+            instrumented: HashMap::from([(path.clone(), (4..=10).collect())]),
+            has_doctests: false,
+        };
+        // Only the first test executes any line of the function.
+        assert_eq!(
+            test_coverage.covering_tests(&mutants[2]),
+            Some(vec!["test::test_factorial".to_owned()])
+        );
+        // No test executes the function.
+        test_coverage.hits.clear();
+        assert_eq!(test_coverage.covering_tests(&mutants[2]), Some(vec![]));
+        // But doctests might.
+        test_coverage.has_doctests = true;
+        assert_eq!(test_coverage.covering_tests(&mutants[2]), None);
+        // The function is not instrumented, so it's not known which tests execute it.
+        test_coverage.instrumented = HashMap::from([(path, (20..=30).collect())]);
+        assert_eq!(test_coverage.covering_tests(&mutants[2]), None);
+    }
+
+    #[test]
+    fn find_test_executables() {
+        let output = indoc! { r#"
+            run cargo test --no-run --message-format=json
+            {"reason":"compiler-artifact","profile":{"test":false},"executable":null,"target":{"kind":["lib"]}}
+            {"reason":"compiler-artifact","profile":{"test":true},"executable":"/t/debug/deps/lib-abc","target":{"kind":["lib"]}}
+            {"reason":"compiler-artifact","profile":{"test":true},"executable":"/t/debug/deps/api-def","target":{"kind":["test"]}}
+            {"reason":"build-finished","success":true}
+            result: Success
+        "# };
+        assert_eq!(
+            test_executables(output),
+            [
+                Utf8PathBuf::from("/t/debug/deps/lib-abc"),
+                Utf8PathBuf::from("/t/debug/deps/api-def")
+            ]
+        );
+    }
+
+    #[test]
+    fn find_test_names() {
+        let output = indoc! { "
+            run cargo test -- --list --format=terse
+                Finished `test` profile [unoptimized + debuginfo] target(s) in 0.04s
+                 Running unittests src/lib.rs (target/debug/deps/lib-abc)
+            test::test_factorial: test
+            test::bench_factorial: bench
+               Doc-tests lib
+            src/lib.rs - factorial (line 3): test
+            double_is_even: test
+            test::test_factorial: test
+        " };
+        assert_eq!(
+            test_names(output),
+            ["double_is_even", "test::test_factorial"]
+        );
+        assert!(listed_tests(output).any(is_doctest));
+        assert!(!listed_tests("a: test\nb: test\n").any(is_doctest));
+    }
+}
@@ -2,32 +2,510 @@
 
 //! Mutations of replacing a function body with a value of a (hopefully) appropriate type.
 
+use std::collections::{HashMap, HashSet};
 use std::iter;
+use std::sync::Arc;
 
+use anyhow::{ensure, Context};
 use itertools::Itertools;
-use proc_macro2::TokenStream;
-use quote::quote;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::visit::Visit;
 use syn::{
-    AngleBracketedGenericArguments, AssocType, Expr, GenericArgument, Ident, Path, PathArguments,
-    ReturnType, TraitBound, Type, TypeArray, TypeImplTrait, TypeParamBound, TypeSlice, TypeTuple,
+    AngleBracketedGenericArguments, AssocType, Expr, GenericArgument, Generics, Ident,
+    ParenthesizedGenericArguments, Path, PathArguments, PathSegment, PredicateType, ReturnType,
+    Token, TraitBound, Type, TypeArray, TypeImplTrait, TypeParamBound, TypePath, TypeSlice,
+    TypeTraitObject, TypeTuple, UseTree, WherePredicate,
 };
-use tracing::trace;
+use tracing::{trace, warn};
+
+use crate::pretty::ToPrettyString;
+use crate::Result;
+
+/// Information about the tree and the file being visited that's used to generate
+/// replacement values.
+#[derive(Debug, Default)]
+pub(crate) struct ReplacementContext {
+    /// Parsed error expressions, from the config file or command line.
+    pub error_exprs: Vec<Expr>,
+
+    /// Types defined in the package containing the file.
+    pub local_types: Arc<LocalTypes>,
+
+    /// Names imported into the file.
+    pub imports: Imports,
+
+    /// Replacements for types matching patterns, from the config file.
+    pub replacement_rules: Vec<ReplacementRule>,
+}
+
+/// Generic type parameters in scope in a function, with the traits that bound them.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TypeParams {
+    bounds: HashMap<String, Vec<Path>>,
+}
+
+impl TypeParams {
+    /// Add the type parameters declared by some generics, including bounds from
+    /// the where clause, to those that are already in scope.
+    pub fn with_generics(&self, generics: &Generics) -> TypeParams {
+        let mut bounds = self.bounds.clone();
+        for type_param in generics.type_params() {
+            bounds.insert(
+                type_param.ident.to_string(),
+                trait_paths(&type_param.bounds).collect(),
+            );
+        }
+        for predicate in generics.where_clause.iter().flat_map(|w| &w.predicates) {
+            if let WherePredicate::Type(PredicateType {
+                bounded_ty: Type::Path(TypePath { qself: None, path }),
+                bounds: predicate_bounds,
+                ..
+            }) = predicate
+            {
+                if let Some(param_bounds) = path
+                    .get_ident()
+                    .and_then(|ident| bounds.get_mut(&ident.to_string()))
+                {
+                    param_bounds.extend(trait_paths(predicate_bounds));
+                }
+            }
+        }
+        TypeParams { bounds }
+    }
+
+    /// True if the path is the name of a type parameter.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.get(path).is_some()
+    }
+
+    fn get(&self, path: &Path) -> Option<&[Path]> {
+        self.bounds
+            .get(&path.get_ident()?.to_string())
+            .map(Vec::as_slice)
+    }
+}
+
+/// Names imported by `use` items.
+///
+/// Items are collected from the whole file, without tracking which module or block
+/// they're in, so this only approximates what's in scope.
+#[derive(Debug, Default)]
+pub(crate) struct Imports {
+    /// The full path of each imported name, by the name it's imported as.
+    names: HashMap<String, Vec<String>>,
+    /// Paths whose contents are all imported by a glob, like `use std::time::*`.
+    globs: Vec<Vec<String>>,
+}
+
+impl Imports {
+    /// Collect the names imported anywhere in a file.
+    pub fn in_file(file: &syn::File) -> Imports {
+        let mut imports = Imports::default();
+        imports.visit_file(file);
+        imports
+    }
+
+    fn add_tree(&mut self, prefix: &[String], tree: &UseTree) {
+        let joined = |ident: &Ident| {
+            let mut path = prefix.to_vec();
+            if ident != "self" {
+                path.push(ident.to_string());
+            }
+            path
+        };
+        match tree {
+            UseTree::Path(use_path) => self.add_tree(&joined(&use_path.ident), &use_path.tree),
+            UseTree::Name(use_name) => {
+                let path = joined(&use_name.ident);
+                if let Some(name) = path.last() {
+                    self.names.insert(name.clone(), path);
+                }
+            }
+            UseTree::Rename(use_rename) => {
+                self.names
+                    .insert(use_rename.rename.to_string(), joined(&use_rename.ident));
+            }
+            UseTree::Glob(_) => self.globs.push(prefix.to_vec()),
+            UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.add_tree(prefix, tree);
+                }
+            }
+        }
+    }
+
+    /// The full paths that a path might refer to: as written, and as resolved through
+    /// the imported names and globs.
+    fn resolve(&self, path: &Path) -> Vec<Vec<String>> {
+        let segments = path
+            .segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect_vec();
+        let mut paths = vec![segments.clone()];
+        if path.leading_colon.is_some() {
+            return paths;
+        }
+        if let Some(imported) = self.names.get(&segments[0]) {
+            paths.push(imported.iter().chain(&segments[1..]).cloned().collect());
+        }
+        paths.extend(
+            self.globs
+                .iter()
+                .map(|glob| glob.iter().chain(&segments).cloned().collect()),
+        );
+        paths
+    }
+}
+
+impl Visit<'_> for Imports {
+    fn visit_item_use(&mut self, i: &syn::ItemUse) {
+        self.add_tree(&[], &i.tree);
+    }
+}
+
+fn trait_paths(bounds: &Punctuated<TypeParamBound, Token![+]>) -> impl Iterator<Item = Path> + '_ {
+    bounds.iter().filter_map(|bound| match bound {
+        TypeParamBound::Trait(TraitBound { path, .. }) => Some(path.clone()),
+        _ => None,
+    })
+}
+
+/// A user-configured rule mapping a type pattern, like `Id<T>`, to replacement
+/// expressions, like `Id::new($T)`.
+///
+/// Generic arguments of the pattern that are used as `$T` placeholders in the
+/// expressions match any type, and each placeholder is filled with every replacement
+/// of the type it matched. Other arguments must match exactly.
+#[derive(Debug, Clone)]
+pub(crate) struct ReplacementRule {
+    pattern: Path,
+    /// Names of the generic arguments in the pattern that are placeholders.
+    placeholders: Vec<String>,
+    /// Replacement expressions, which may contain placeholders like `$T`.
+    exprs: Vec<TokenStream>,
+}
+
+impl ReplacementRule {
+    pub fn new(pattern: &str, exprs: &[String]) -> Result<ReplacementRule> {
+        let pattern: Path = syn::parse_str(pattern)
+            .with_context(|| format!("Failed to parse replacement type pattern {pattern:?}"))?;
+        let exprs = exprs
+            .iter()
+            .map(|expr| {
+                syn::parse_str::<TokenStream>(expr)
+                    .with_context(|| format!("Failed to parse replacement expression {expr:?}"))
+            })
+            .collect::<Result<Vec<TokenStream>>>()?;
+        let used = exprs
+            .iter()
+            .flat_map(placeholder_names)
+            .collect::<HashSet<String>>();
+        let placeholders = last_segment_args(&pattern)
+            .into_iter()
+            .filter_map(arg_name)
+            .filter(|name| used.contains(name.as_str()))
+            .collect_vec();
+        for name in &used {
+            ensure!(
+                placeholders.iter().any(|p| p == name),
+                "Replacement for {pattern:?} uses ${name}, which is not a generic argument of the pattern",
+                pattern = pattern.to_pretty_string(),
+            );
+        }
+        for expr in &exprs {
+            let dummy = fill_placeholders(expr.clone(), &mut |_| quote! { x });
+            syn::parse2::<Expr>(dummy).with_context(|| {
+                format!(
+                    "Failed to parse replacement expression {:?}",
+                    expr.to_pretty_string()
+                )
+            })?;
+        }
+        Ok(ReplacementRule {
+            pattern,
+            placeholders,
+            exprs,
+        })
+    }
+
+    /// If the path matches this rule's pattern, return the replacements.
+    fn replacements(
+        &self,
+        path: &Path,
+        context: &ReplacementContext,
+        type_params: &TypeParams,
+    ) -> Option<Vec<TokenStream>> {
+        let bindings = self.match_path(path)?;
+        let inner_reps = self
+            .placeholders
+            .iter()
+            .map(|name| {
+                type_replacements(bindings[name.as_str()], context, type_params).collect_vec()
+            })
+            .collect_vec();
+        let combinations = if inner_reps.is_empty() {
+            vec![vec![]]
+        } else {
+            inner_reps
+                .into_iter()
+                .multi_cartesian_product()
+                .collect_vec()
+        };
+        let mut reps = Vec::new();
+        for expr in &self.exprs {
+            for combination in &combinations {
+                let filled = fill_placeholders(expr.clone(), &mut |name| {
+                    let i = self.placeholders.iter().position(|p| p == name).unwrap();
+                    combination[i].clone()
+                });
+                match syn::parse2::<Expr>(filled.clone()) {
+                    Ok(expr) => reps.push(expr.to_token_stream()),
+                    Err(err) => warn!(
+                        filled = filled.to_pretty_string(),
+                        ?err,
+                        "Failed to parse filled replacement"
+                    ),
+                }
+            }
+        }
+        // Expressions that don't use every placeholder can produce the same text
+        // more than once.
+        Some(reps.into_iter().unique_by(|rep| rep.to_string()).collect())
+    }
+
+    /// Match a type path against the pattern, returning the types bound to each placeholder.
+    ///
+    /// The pattern matches paths that end with the same segments, so `Money` matches
+    /// `bank::Money`.
+    fn match_path<'p>(&self, path: &'p Path) -> Option<HashMap<&str, &'p Type>> {
+        let n = self.pattern.segments.len();
+        if path.segments.len() < n {
+            return None;
+        }
+        let tail = path.segments.iter().skip(path.segments.len() - n);
+        for (pattern_segment, segment) in self.pattern.segments.iter().zip(tail) {
+            if pattern_segment.ident != segment.ident {
+                return None;
+            }
+        }
+        let pattern_args = last_segment_args(&self.pattern);
+        let args = last_segment_args(path);
+        if pattern_args.len() != args.len() {
+            return None;
+        }
+        let mut bindings = HashMap::new();
+        for (pattern_arg, arg) in pattern_args.into_iter().zip(args) {
+            let placeholder = arg_name(pattern_arg)
+                .and_then(|name| self.placeholders.iter().find(|p| **p == name));
+            match (placeholder, arg) {
+                (Some(name), GenericArgument::Type(arg_type)) => {
+                    bindings.insert(name.as_str(), arg_type);
+                }
+                _ => {
+                    if pattern_arg.to_pretty_string() != arg.to_pretty_string() {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(bindings)
+    }
+}
+
+/// The generic arguments on the last segment of a path.
+fn last_segment_args(path: &Path) -> Vec<&GenericArgument> {
+    match path.segments.last().map(|segment| &segment.arguments) {
+        Some(PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. })) => {
+            args.iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// If a generic argument in a pattern is a single name, that might be a placeholder,
+/// return the name.
+fn arg_name(arg: &GenericArgument) -> Option<String> {
+    match arg {
+        GenericArgument::Type(Type::Path(TypePath { qself: None, path })) => {
+            path.get_ident().map(Ident::to_string)
+        }
+        _ => None,
+    }
+}
+
+/// Find the names of `$name` placeholders in a replacement expression.
+fn placeholder_names(expr: &TokenStream) -> Vec<String> {
+    let mut names = Vec::new();
+    fill_placeholders(expr.clone(), &mut |name| {
+        names.push(name.to_owned());
+        TokenStream::new()
+    });
+    names
+}
+
+/// Replace each `$name` placeholder in an expression with the tokens for that name.
+///
+/// A `$` inside a string or character literal is part of the literal token, so it's
+/// not mistaken for a placeholder.
+fn fill_placeholders(expr: TokenStream, fill: &mut dyn FnMut(&str) -> TokenStream) -> TokenStream {
+    let mut filled = TokenStream::new();
+    let mut tokens = expr.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                if let Some(TokenTree::Ident(name)) = tokens.peek() {
+                    let name = name.to_string();
+                    tokens.next();
+                    filled.extend(fill(&name));
+                } else {
+                    filled.extend([TokenTree::Punct(punct)]);
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut new_group =
+                    Group::new(group.delimiter(), fill_placeholders(group.stream(), fill));
+                new_group.set_span(group.span());
+                filled.extend([TokenTree::Group(new_group)]);
+            }
+            tt => filled.extend([tt]),
+        }
+    }
+    filled
+}
+
+/// Types defined in one package, which help to generate better replacement values.
+///
+/// Types are only known by name, so types of the same name in different modules of
+/// the package are not distinguished.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LocalTypes {
+    /// Names of the structs, enums, and unions defined in the package.
+    pub names: HashSet<String>,
+
+    /// Names of types that implement `Default`, or have a derive that might implement it.
+    pub with_default: HashSet<String>,
+
+    /// Enums defined in the package, by name, with the names of their unit variants.
+    pub enums: HashMap<String, Vec<String>>,
+
+    /// Names of the modules defined in the package.
+    pub mods: HashSet<String>,
+}
+
+impl LocalTypes {
+    /// Remember an enum and its unit variants.
+    ///
+    /// If there are several enums with the same name, only the variants they
+    /// have in common are used, since we don't know which one is meant.
+    pub fn add_enum(&mut self, name: String, unit_variants: Vec<String>) {
+        self.names.insert(name.clone());
+        self.enums
+            .entry(name)
+            .and_modify(|existing| existing.retain(|v| unit_variants.contains(v)))
+            .or_insert(unit_variants);
+    }
+
+    /// Add all the types found in another file.
+    pub fn extend(&mut self, other: LocalTypes) {
+        self.names.extend(other.names);
+        self.with_default.extend(other.with_default);
+        self.mods.extend(other.mods);
+        for (name, variants) in other.enums {
+            self.add_enum(name, variants);
+        }
+    }
+
+    /// If a type path might refer to a type defined in the package, return its name.
+    ///
+    /// Types are only known by their names, so the path must be a bare name, or start
+    /// with `crate`, `self`, `super`, or a module defined in the package. This means that,
+    /// for example, `chrono::Duration` isn't mistaken for a local type called `Duration`.
+    pub fn local_name(&self, path: &Path) -> Option<String> {
+        let name = path.segments.last()?.ident.to_string();
+        if !self.names.contains(&name) || path.leading_colon.is_some() {
+            return None;
+        }
+        let first = path.segments.first()?.ident.to_string();
+        if path.segments.len() == 1
+            || ["crate", "self", "super"].contains(&first.as_str())
+            || self.mods.contains(&first)
+        {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    /// True if a type of this name is defined in the package, and definitely doesn't
+    /// implement `Default`.
+    pub fn lacks_default(&self, name: &str) -> bool {
+        self.names.contains(name) && !self.with_default.contains(name)
+    }
+}
 
 /// Generate replacement text for a function based on its return type.
 pub(crate) fn return_type_replacements(
     return_type: &ReturnType,
-    error_exprs: &[Expr],
+    context: &ReplacementContext,
+    type_params: &TypeParams,
 ) -> Vec<TokenStream> {
     match return_type {
         ReturnType::Default => vec![quote! { () }],
-        ReturnType::Type(_rarrow, type_) => type_replacements(type_, error_exprs).collect_vec(),
+        ReturnType::Type(_rarrow, type_) => {
+            type_replacements(type_, context, type_params).collect_vec()
+        }
+    }
+}
+
+/// Generate replacement values of a type, for example for the value of a constant.
+pub(crate) fn value_replacements(
+    type_: &Type,
+    context: &ReplacementContext,
+    type_params: &TypeParams,
+) -> Vec<TokenStream> {
+    type_replacements(type_, context, type_params).collect_vec()
+}
+
+/// Generate a replacement for `expr?` that drops the error and instead returns
+/// early with a successful value, for functions that return `Result` or `Option`.
+///
+/// Returns None if the function returns some other type.
+pub(crate) fn drop_error_replacement(
+    return_type: &ReturnType,
+    expr: &Expr,
+    context: &ReplacementContext,
+    type_params: &TypeParams,
+) -> Option<TokenStream> {
+    let ReturnType::Type(_rarrow, type_) = return_type else {
+        return None;
+    };
+    let Type::Path(syn::TypePath { path, .. }) = &**type_ else {
+        return None;
+    };
+    if path_ends_with(path, "Result") {
+        let rep = type_replacements(type_, context, type_params).next()?;
+        Some(quote! { match #expr { Ok(v) => v, Err(_) => return #rep } })
+    } else if match_first_type_arg(path, "Option").is_some() {
+        // The first replacement for an Option is `None`, which is what `?` would
+        // have returned anyhow.
+        let rep = type_replacements(type_, context, type_params).nth(1)?;
+        Some(quote! { match #expr { Some(v) => v, None => return #rep } })
+    } else {
+        None
     }
 }
 
 /// Generate some values that we hope are reasonable replacements for a type.
 ///
 /// This is really the heart of cargo-mutants.
-fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item = TokenStream> {
+fn type_replacements(
+    type_: &Type,
+    context: &ReplacementContext,
+    type_params: &TypeParams,
+) -> impl Iterator<Item = TokenStream> {
     // This could probably change to run from some configuration rather than
     // hardcoding various types, which would make it easier to support tree-specific
     // mutation values, and perhaps reduce duplication. However, it seems better
@@ -36,7 +514,15 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
     match type_ {
         Type::Path(syn::TypePath { path, .. }) => {
             // dbg!(&path);
-            if path.is_ident("bool") {
+            if let Some(bounds) = type_params.get(path) {
+                type_param_replacements(path, bounds, context, type_params)
+            } else if let Some(reps) = context
+                .replacement_rules
+                .iter()
+                .find_map(|rule| rule.replacements(path, context, type_params))
+            {
+                reps
+            } else if path.is_ident("bool") {
                 vec![quote! { true }, quote! { false }]
             } else if path.is_ident("String") {
                 vec![quote! { String::new() }, quote! { "xyzzy".into() }]
@@ -52,9 +538,13 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
                 vec![quote! { 1 }]
             } else if path_is_float(path) {
                 vec![quote! { 0.0 }, quote! { 1.0 }, quote! { -1.0 }]
+            } else if path.is_ident("char") {
+                vec![quote! { '\0' }, quote! { 'x' }]
+            } else if let Some(reps) = known_value_type(path, context) {
+                reps
             } else if path_ends_with(path, "Result") {
                 if let Some(ok_type) = match_first_type_arg(path, "Result") {
-                    type_replacements(ok_type, error_exprs)
+                    type_replacements(ok_type, context, type_params)
                         .map(|rep| {
                             quote! { Ok(#rep) }
                         })
@@ -65,7 +555,7 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
                     vec![quote! { Ok(Default::default()) }]
                 }
                 .into_iter()
-                .chain(error_exprs.iter().map(|error_expr| {
+                .chain(context.error_exprs.iter().map(|error_expr| {
                     quote! { Err(#error_expr) }
                 }))
                 .collect_vec()
@@ -73,23 +563,36 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
                 vec![quote! { HttpResponse::Ok().finish() }]
             } else if let Some(some_type) = match_first_type_arg(path, "Option") {
                 iter::once(quote! { None })
-                    .chain(type_replacements(some_type, error_exprs).map(|rep| {
-                        quote! { Some(#rep) }
-                    }))
+                    .chain(
+                        type_replacements(some_type, context, type_params).map(|rep| {
+                            quote! { Some(#rep) }
+                        }),
+                    )
                     .collect_vec()
             } else if let Some(element_type) = match_first_type_arg(path, "Vec") {
                 // Generate an empty Vec, and then a one-element vec for every recursive
                 // value.
                 iter::once(quote! { vec![] })
-                    .chain(type_replacements(element_type, error_exprs).map(|rep| {
-                        quote! { vec![#rep] }
-                    }))
+                    .chain(
+                        type_replacements(element_type, context, type_params).map(|rep| {
+                            quote! { vec![#rep] }
+                        }),
+                    )
+                    .collect_vec()
+            } else if let Some(index_type) = match_first_type_arg(path, "Range") {
+                // Ranges between every pair of replacement values, including empty ranges.
+                let reps = type_replacements(index_type, context, type_params).collect_vec();
+                reps.iter()
+                    .cartesian_product(&reps)
+                    .map(|(start, end)| quote! { #start..#end })
                     .collect_vec()
+            } else if match_boxed_error(path) {
+                vec![quote! { "mutated".into() }]
             } else if let Some(borrowed_type) = match_first_type_arg(path, "Cow") {
                 // TODO: We could specialize Cows for cases like Vec and Box where
                 // we would have to leak to make the reference; perhaps it would only
                 // look better...
-                type_replacements(borrowed_type, error_exprs)
+                type_replacements(borrowed_type, context, type_params)
                     .flat_map(|rep| {
                         [
                             quote! { Cow::Borrowed(#rep) },
@@ -97,26 +600,34 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
                         ]
                     })
                     .collect_vec()
+            } else if let Some(output_type) = match_boxed_future(path) {
+                // What `async-trait` and similar macros return for `async fn`s, and a
+                // common way to return a future from a non-async function.
+                type_replacements(output_type, context, type_params)
+                    .map(|rep| quote! { Box::pin(async { #rep }) })
+                    .collect_vec()
             } else if let Some((container_type, inner_type)) = known_container(path) {
                 // Something like Arc, Mutex, etc.
                 // TODO: Ideally we should use the path without relying on it being
                 // imported, but we must strip or rewrite the arguments, so that
                 // `std::sync::Arc<String>` becomes either `std::sync::Arc::<String>::new`
                 // or at least `std::sync::Arc::new`. Similarly for other types.
-                type_replacements(inner_type, error_exprs)
+                type_replacements(inner_type, context, type_params)
                     .map(|rep| {
                         quote! { #container_type::new(#rep) }
                     })
                     .collect_vec()
             } else if let Some((collection_type, inner_type)) = known_collection(path) {
                 iter::once(quote! { #collection_type::new() })
-                    .chain(type_replacements(inner_type, error_exprs).map(|rep| {
-                        quote! { #collection_type::from_iter([#rep]) }
-                    }))
+                    .chain(
+                        type_replacements(inner_type, context, type_params).map(|rep| {
+                            quote! { #collection_type::from_iter([#rep]) }
+                        }),
+                    )
                     .collect_vec()
             } else if let Some((collection_type, key_type, value_type)) = known_map(path) {
-                let key_reps = type_replacements(key_type, error_exprs).collect_vec();
-                let val_reps = type_replacements(value_type, error_exprs).collect_vec();
+                let key_reps = type_replacements(key_type, context, type_params).collect_vec();
+                let val_reps = type_replacements(value_type, context, type_params).collect_vec();
                 iter::once(quote! { #collection_type::new() })
                     .chain(
                         key_reps
@@ -131,14 +642,27 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
                 // to call it, but we strongly suspect that you could construct it from
                 // an `A`.
                 iter::once(quote! { #collection_type::new() })
-                    .chain(type_replacements(inner_type, error_exprs).flat_map(|rep| {
-                        [
-                            quote! { #collection_type::from_iter([#rep]) },
-                            quote! { #collection_type::new(#rep) },
-                            quote! { #collection_type::from(#rep) },
-                        ]
-                    }))
+                    .chain(
+                        type_replacements(inner_type, context, type_params).flat_map(|rep| {
+                            [
+                                quote! { #collection_type::from_iter([#rep]) },
+                                quote! { #collection_type::new(#rep) },
+                                quote! { #collection_type::from(#rep) },
+                            ]
+                        }),
+                    )
                     .collect_vec()
+            } else if let Some(variants) = match_local_enum(path, context) {
+                variants
+                    .iter()
+                    .map(|variant| {
+                        let variant = Ident::new(variant, Span::call_site());
+                        quote! { #path::#variant }
+                    })
+                    .collect_vec()
+            } else if path_is_local_type_lacking_default(path, context) {
+                trace!(?type_, "Local type has no Default");
+                vec![]
             } else {
                 trace!(?type_, "Return type is not recognized, trying Default");
                 vec![quote! { Default::default() }]
@@ -150,12 +674,15 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
         // large, and values like "all zeros" and "all ones" seem likely to catch
         // lots of things.
         {
-            type_replacements(elem, error_exprs)
+            type_replacements(elem, context, type_params)
                 .map(|r| quote! { [ #r; #len ] })
                 .collect_vec()
         }
         Type::Slice(TypeSlice { elem, .. }) => iter::once(quote! { Vec::leak(Vec::new()) })
-            .chain(type_replacements(elem, error_exprs).map(|r| quote! { Vec::leak(vec![ #r ]) }))
+            .chain(
+                type_replacements(elem, context, type_params)
+                    .map(|r| quote! { Vec::leak(vec![ #r ]) }),
+            )
             .collect_vec(),
         Type::Reference(syn::TypeReference {
             mutability: None,
@@ -166,12 +693,18 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
             Type::Path(path) if path.path.is_ident("str") => {
                 vec![quote! { "" }, quote! { "xyzzy" }]
             }
+            Type::Path(TypePath { path, .. })
+                if path_names_type(path, context, &["std::path::Path"]) =>
+            {
+                vec![quote! { #path::new("") }, quote! { #path::new("xyzzy") }]
+            }
             Type::Slice(TypeSlice { elem, .. }) => iter::once(quote! { Vec::leak(Vec::new()) })
                 .chain(
-                    type_replacements(elem, error_exprs).map(|r| quote! { Vec::leak(vec![ #r ]) }),
+                    type_replacements(elem, context, type_params)
+                        .map(|r| quote! { Vec::leak(vec![ #r ]) }),
                 )
                 .collect_vec(),
-            _ => type_replacements(elem, error_exprs)
+            _ => type_replacements(elem, context, type_params)
                 .map(|rep| {
                     quote! { &#rep }
                 })
@@ -184,12 +717,13 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
         }) => match &**elem {
             Type::Slice(TypeSlice { elem, .. }) => iter::once(quote! { Vec::leak(Vec::new()) })
                 .chain(
-                    type_replacements(elem, error_exprs).map(|r| quote! { Vec::leak(vec![ #r ]) }),
+                    type_replacements(elem, context, type_params)
+                        .map(|r| quote! { Vec::leak(vec![ #r ]) }),
                 )
                 .collect_vec(),
             _ => {
                 // Make &mut with static lifetime by leaking them on the heap.
-                type_replacements(elem, error_exprs)
+                type_replacements(elem, context, type_params)
                     .map(|rep| {
                         quote! { Box::leak(Box::new(#rep)) }
                     })
@@ -203,22 +737,35 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
             // Generate the cartesian product of replacements of every type within the tuple.
             elems
                 .iter()
-                .map(|elem| type_replacements(elem, error_exprs).collect_vec())
+                .map(|elem| type_replacements(elem, context, type_params).collect_vec())
                 .multi_cartesian_product()
                 .map(|reps| {
                     quote! { ( #( #reps ),* ) }
                 })
                 .collect_vec()
         }
-        // -> impl Iterator<Item = T>
+        // -> impl Iterator<Item = T>, and similar traits
         Type::ImplTrait(impl_trait) => {
             if let Some(item_type) = match_impl_iterator(impl_trait) {
                 iter::once(quote! { ::std::iter::empty() })
                     .chain(
-                        type_replacements(item_type, error_exprs)
+                        type_replacements(item_type, context, type_params)
                             .map(|r| quote! { ::std::iter::once(#r) }),
                     )
                     .collect_vec()
+            } else if let Some((n_inputs, output)) = match_impl_fn(impl_trait) {
+                // A closure ignoring all its arguments and returning a fixed value.
+                let args = iter::repeat(quote! { _ }).take(n_inputs).collect_vec();
+                return_type_replacements(output, context, type_params)
+                    .into_iter()
+                    .map(|rep| quote! { |#( #args ),*| #rep })
+                    .collect_vec()
+            } else if let Some(output_type) = match_impl_future(impl_trait) {
+                type_replacements(output_type, context, type_params)
+                    .map(|rep| quote! { async { #rep } })
+                    .collect_vec()
+            } else if trait_bound(&impl_trait.bounds, &["Display", "ToString"]).is_some() {
+                vec![quote! { "" }, quote! { "xyzzy" }]
             } else {
                 // TODO: Can we do anything with other impl traits?
                 vec![]
@@ -235,33 +782,144 @@ fn type_replacements(type_: &Type, error_exprs: &[Expr]) -> impl Iterator<Item =
     .into_iter()
 }
 
+/// Generate values of a generic type parameter, using only constructors that its
+/// trait bounds guarantee exist.
+///
+/// If there are no suitable bounds this returns nothing: `Default::default()` would
+/// not compile.
+fn type_param_replacements(
+    param: &Path,
+    bounds: &[Path],
+    context: &ReplacementContext,
+    type_params: &TypeParams,
+) -> Vec<TokenStream> {
+    let mut reps = Vec::new();
+    for bound in bounds {
+        if path_ends_with(bound, "Default") {
+            reps.push(quote! { Default::default() });
+        } else if path_ends_with(bound, "Zero") {
+            reps.push(quote! { #param::zero() });
+        } else if path_ends_with(bound, "One") {
+            reps.push(quote! { #param::one() });
+        } else if ["Num", "PrimInt", "Float", "Signed", "Unsigned"]
+            .iter()
+            .any(|name| path_ends_with(bound, name))
+        {
+            reps.extend([quote! { #param::zero() }, quote! { #param::one() }]);
+        } else if let Some(from_type) = match_first_type_arg(bound, "From") {
+            reps.extend(
+                type_replacements(from_type, context, type_params)
+                    .map(|rep| quote! { #param::from(#rep) }),
+            );
+        }
+    }
+    reps.into_iter().unique_by(|rep| rep.to_string()).collect()
+}
+
+/// If this path names an enum defined in the tree, return the names of its unit variants.
+fn match_local_enum<'c>(path: &Path, context: &'c ReplacementContext) -> Option<&'c [String]> {
+    if !path.segments.last()?.arguments.is_none() {
+        return None;
+    }
+    context
+        .local_types
+        .enums
+        .get(&context.local_types.local_name(path)?)
+        .map(Vec::as_slice)
+        .filter(|variants| !variants.is_empty())
+}
+
+/// True if the path names a type defined in the tree that doesn't implement `Default`.
+fn path_is_local_type_lacking_default(path: &Path, context: &ReplacementContext) -> bool {
+    context
+        .local_types
+        .local_name(path)
+        .is_some_and(|name| context.local_types.lacks_default(&name))
+}
+
 fn path_ends_with(path: &Path, ident: &str) -> bool {
     path.segments.last().map_or(false, |s| s.ident == ident)
 }
 
-fn match_impl_iterator(TypeImplTrait { bounds, .. }: &TypeImplTrait) -> Option<&Type> {
-    for bound in bounds {
-        if let TypeParamBound::Trait(TraitBound { path, .. }) = bound {
-            if let Some(last_segment) = path.segments.last() {
-                if last_segment.ident == "Iterator" {
-                    if let PathArguments::AngleBracketed(AngleBracketedGenericArguments {
-                        args,
-                        ..
-                    }) = &last_segment.arguments
-                    {
-                        if let Some(GenericArgument::AssocType(AssocType { ident, ty, .. })) =
-                            args.first()
-                        {
-                            if ident == "Item" {
-                                return Some(ty);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+/// Find the first trait bound in an `impl Trait` or `dyn Trait` type whose last
+/// path segment is one of the given names.
+fn trait_bound<'t>(
+    bounds: &'t Punctuated<TypeParamBound, Token![+]>,
+    trait_names: &[&str],
+) -> Option<&'t PathSegment> {
+    bounds.iter().find_map(|bound| match bound {
+        TypeParamBound::Trait(TraitBound { path, .. }) => path
+            .segments
+            .last()
+            .filter(|segment| trait_names.iter().any(|name| segment.ident == name)),
+        _ => None,
+    })
+}
+
+/// Find the type bound to an associated type name, like `Item = T`, in the
+/// arguments of a trait path segment.
+fn assoc_type<'s>(segment: &'s PathSegment, name: &str) -> Option<&'s Type> {
+    if let PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) =
+        &segment.arguments
+    {
+        args.iter().find_map(|arg| match arg {
+            GenericArgument::AssocType(AssocType { ident, ty, .. }) if ident == name => Some(ty),
+            _ => None,
+        })
+    } else {
+        None
     }
-    None
+}
+
+/// Match `impl Iterator<Item = T>`, or a similar trait that can be satisfied
+/// by an empty or one-element iterator, and return `T`.
+fn match_impl_iterator(impl_trait: &TypeImplTrait) -> Option<&Type> {
+    let segment = trait_bound(
+        &impl_trait.bounds,
+        &[
+            "Iterator",
+            "IntoIterator",
+            "DoubleEndedIterator",
+            "ExactSizeIterator",
+        ],
+    )?;
+    assoc_type(segment, "Item")
+}
+
+/// Match `impl Fn(A, B) -> R`, or `FnMut` or `FnOnce`, and return the number of
+/// arguments and the return type.
+fn match_impl_fn(impl_trait: &TypeImplTrait) -> Option<(usize, &ReturnType)> {
+    let segment = trait_bound(&impl_trait.bounds, &["Fn", "FnMut", "FnOnce"])?;
+    if let PathArguments::Parenthesized(ParenthesizedGenericArguments { inputs, output, .. }) =
+        &segment.arguments
+    {
+        Some((inputs.len(), output))
+    } else {
+        None
+    }
+}
+
+/// Match `impl Future<Output = T>` and return `T`.
+fn match_impl_future(impl_trait: &TypeImplTrait) -> Option<&Type> {
+    assoc_type(trait_bound(&impl_trait.bounds, &["Future"])?, "Output")
+}
+
+/// Match a boxed future, `Pin<Box<dyn Future<Output = T>>>`, or `BoxFuture<'_, T>`
+/// or `LocalBoxFuture<'_, T>` from the `futures` crate, and return `T`.
+fn match_boxed_future(path: &Path) -> Option<&Type> {
+    if let Some(output_type) = match_first_type_arg(path, "BoxFuture")
+        .or_else(|| match_first_type_arg(path, "LocalBoxFuture"))
+    {
+        return Some(output_type);
+    }
+    let Type::Path(TypePath { path: box_path, .. }) = match_first_type_arg(path, "Pin")? else {
+        return None;
+    };
+    let Type::TraitObject(TypeTraitObject { bounds, .. }) = match_first_type_arg(box_path, "Box")?
+    else {
+        return None;
+    };
+    assoc_type(trait_bound(bounds, &["Future"])?, "Output")
 }
 
 /// If the type has a single type argument then, perhaps it's a simple container
@@ -362,6 +1020,77 @@ fn maybe_collection_or_container(path: &Path) -> Option<(&Ident, &Type)> {
     None
 }
 
+/// True if the path names one of the given types, like `std::time::Duration`, without
+/// type arguments.
+///
+/// The path can be written out in full, or start with a name imported into the file:
+/// for example, `Duration` matches after `use std::time::Duration`, and `time::Duration`
+/// after `use std::time`, but not after `use time::Duration`, which is another crate.
+fn path_names_type(path: &Path, context: &ReplacementContext, full_names: &[&str]) -> bool {
+    if path.segments.iter().any(|s| !s.arguments.is_none()) {
+        return false;
+    }
+    context.imports.resolve(path).iter().any(|resolved| {
+        full_names.iter().any(|full_name| {
+            full_name
+                .split("::")
+                .eq(resolved.iter().map(String::as_str))
+        })
+    })
+}
+
+/// Replacements for some common types from the standard library and popular crates,
+/// that can't be made from `Default::default()` or would only give one value.
+///
+/// The replacements use the path as written in the source, so they work whether or
+/// not the type is imported.
+///
+/// Types defined in the tree take precedence, in case they have the same name.
+fn known_value_type(path: &Path, context: &ReplacementContext) -> Option<Vec<TokenStream>> {
+    if context.local_types.local_name(path).is_some() {
+        return None;
+    }
+    let path_is = |full_names: &[&str]| path_names_type(path, context, full_names);
+    let reps = if path_is(&["std::path::PathBuf", "std::ffi::OsString"]) {
+        vec![quote! { #path::new() }, quote! { #path::from("xyzzy") }]
+    } else if path_is(&["std::time::Duration", "core::time::Duration"]) {
+        vec![
+            quote! { #path::ZERO },
+            quote! { #path::from_secs(1) },
+            quote! { #path::MAX },
+        ]
+    } else if path_is(&["std::time::Instant"]) {
+        vec![quote! { #path::now() }]
+    } else if path_is(&["std::time::SystemTime"]) {
+        vec![quote! { #path::now() }, quote! { #path::UNIX_EPOCH }]
+    } else if path_is(&["std::cmp::Ordering", "core::cmp::Ordering"]) {
+        vec![
+            quote! { #path::Less },
+            quote! { #path::Equal },
+            quote! { #path::Greater },
+        ]
+    } else if path_is(&["bytes::Bytes"]) {
+        vec![
+            quote! { #path::new() },
+            quote! { #path::from_static(b"xyzzy") },
+        ]
+    } else if path_is(&["serde_json::Value"]) {
+        vec![quote! { #path::Null }]
+    } else {
+        return None;
+    };
+    Some(reps)
+}
+
+/// Match `Box<dyn Error>`, perhaps with extra bounds like `Send + Sync`.
+fn match_boxed_error(path: &Path) -> bool {
+    matches!(
+        match_first_type_arg(path, "Box"),
+        Some(Type::TraitObject(TypeTraitObject { bounds, .. }))
+            if trait_bound(bounds, &["Error"]).is_some()
+    )
+}
+
 fn path_is_float(path: &Path) -> bool {
     ["f32", "f64"].iter().any(|s| path.is_ident(s))
 }
@@ -433,14 +1162,19 @@ fn match_first_type_arg<'p>(path: &'p Path, expected_ident: &str) -> Option<&'p
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use itertools::Itertools;
     use pretty_assertions::assert_eq;
-    use syn::{parse_quote, Expr, ReturnType};
+    use syn::{parse_quote, Expr, Generics, ReturnType};
 
     use crate::fnvalue::match_impl_iterator;
     use crate::pretty::ToPrettyString;
 
-    use super::{known_map, return_type_replacements};
+    use super::{
+        drop_error_replacement, known_map, return_type_replacements, Imports, LocalTypes,
+        ReplacementContext, ReplacementRule, TypeParams,
+    };
 
     #[test]
     fn recurse_into_result_bool() {
@@ -733,6 +1467,452 @@ mod test {
         );
     }
 
+    #[test]
+    fn impl_iterator_like_replacements() {
+        for trait_name in ["IntoIterator", "DoubleEndedIterator", "ExactSizeIterator"] {
+            check_replacements(
+                syn::parse_str(&format!("-> impl {trait_name}<Item = u8>")).unwrap(),
+                &[],
+                &[
+                    "::std::iter::empty()",
+                    "::std::iter::once(0)",
+                    "::std::iter::once(1)",
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn impl_fn_replacement() {
+        check_replacements(
+            parse_quote! { -> impl Fn(u32, &str) -> bool },
+            &[],
+            &["|_, _| true", "|_, _| false"],
+        );
+        check_replacements(
+            parse_quote! { -> impl FnMut() -> Option<u8> + Send },
+            &[],
+            &["|| None", "|| Some(0)", "|| Some(1)"],
+        );
+        check_replacements(parse_quote! { -> impl FnOnce(String) }, &[], &["|_| ()"]);
+    }
+
+    #[test]
+    fn impl_display_replacement() {
+        check_replacements(
+            parse_quote! { -> impl std::fmt::Display },
+            &[],
+            &[r#""""#, r#""xyzzy""#],
+        );
+        check_replacements(
+            parse_quote! { -> impl ToString + '_ },
+            &[],
+            &[r#""""#, r#""xyzzy""#],
+        );
+    }
+
+    #[test]
+    fn impl_future_replacement() {
+        check_replacements(
+            parse_quote! { -> impl Future<Output = Result<u8, Error>> + Send },
+            &[],
+            &["async {Ok(0)}", "async {Ok(1)}"],
+        );
+        check_replacements(
+            parse_quote! { -> impl std::future::Future<Output = ()> },
+            &[],
+            &["async {()}"],
+        );
+    }
+
+    #[test]
+    fn boxed_future_replacement() {
+        check_replacements(
+            parse_quote! { -> Pin<Box<dyn Future<Output = bool> + Send + 'async_trait>> },
+            &[],
+            &["Box::pin(async {true})", "Box::pin(async {false})"],
+        );
+        check_replacements(
+            parse_quote! { -> std::pin::Pin<Box<dyn std::future::Future<Output = ()>>> },
+            &[],
+            &["Box::pin(async {()})"],
+        );
+        check_replacements(
+            parse_quote! { -> BoxFuture<'a, Option<String>> },
+            &[],
+            &[
+                "Box::pin(async {None})",
+                "Box::pin(async {Some(String::new())})",
+                r#"Box::pin(async {Some("xyzzy".into())})"#,
+            ],
+        );
+    }
+
+    #[test]
+    fn char_replacement() {
+        check_replacements(parse_quote! { -> char }, &[], &[r"'\0'", "'x'"]);
+    }
+
+    #[test]
+    fn path_replacements() {
+        check_imported_replacements(
+            parse_quote! { use std::path::PathBuf; },
+            parse_quote! { -> PathBuf },
+            &["PathBuf::new()", r#"PathBuf::from("xyzzy")"#],
+        );
+        check_replacements(
+            parse_quote! { -> std::path::PathBuf },
+            &[],
+            &[
+                "std::path::PathBuf::new()",
+                r#"std::path::PathBuf::from("xyzzy")"#,
+            ],
+        );
+        check_imported_replacements(
+            parse_quote! { use std::path::{self, Path}; },
+            parse_quote! { -> &'a Path },
+            &[r#"Path::new("")"#, r#"Path::new("xyzzy")"#],
+        );
+        check_replacements(
+            parse_quote! { -> &std::path::Path },
+            &[],
+            &[
+                r#"std::path::Path::new("")"#,
+                r#"std::path::Path::new("xyzzy")"#,
+            ],
+        );
+        check_imported_replacements(
+            parse_quote! { use std::ffi::*; },
+            parse_quote! { -> OsString },
+            &["OsString::new()", r#"OsString::from("xyzzy")"#],
+        );
+    }
+
+    #[test]
+    fn time_replacements() {
+        check_imported_replacements(
+            parse_quote! { use std::time::Duration; },
+            parse_quote! { -> Duration },
+            &["Duration::ZERO", "Duration::from_secs(1)", "Duration::MAX"],
+        );
+        check_replacements(
+            parse_quote! { -> std::time::Instant },
+            &[],
+            &["std::time::Instant::now()"],
+        );
+        check_imported_replacements(
+            parse_quote! { use std::{path::Path, time::{Instant, SystemTime}}; },
+            parse_quote! { -> SystemTime },
+            &["SystemTime::now()", "SystemTime::UNIX_EPOCH"],
+        );
+        check_imported_replacements(
+            parse_quote! { use core::time::Duration as StdDuration; },
+            parse_quote! { -> StdDuration },
+            &[
+                "StdDuration::ZERO",
+                "StdDuration::from_secs(1)",
+                "StdDuration::MAX",
+            ],
+        );
+        // Some other crate's Duration is not matched.
+        check_replacements(
+            parse_quote! { -> chrono::Duration },
+            &[],
+            &["Default::default()"],
+        );
+        check_replacements(
+            parse_quote! { -> time::Duration },
+            &[],
+            &["Default::default()"],
+        );
+        check_imported_replacements(
+            parse_quote! { use time::Duration; },
+            parse_quote! { -> Duration },
+            &["Default::default()"],
+        );
+        // Nor is a bare name that isn't imported.
+        check_replacements(parse_quote! { -> Duration }, &[], &["Default::default()"]);
+    }
+
+    #[test]
+    fn ordering_replacement() {
+        check_imported_replacements(
+            parse_quote! { use std::cmp::Ordering; },
+            parse_quote! { -> Ordering },
+            &["Ordering::Less", "Ordering::Equal", "Ordering::Greater"],
+        );
+        check_imported_replacements(
+            parse_quote! { use std::cmp; },
+            parse_quote! { -> cmp::Ordering },
+            &[
+                "cmp::Ordering::Less",
+                "cmp::Ordering::Equal",
+                "cmp::Ordering::Greater",
+            ],
+        );
+        check_replacements(
+            parse_quote! { -> core::cmp::Ordering },
+            &[],
+            &[
+                "core::cmp::Ordering::Less",
+                "core::cmp::Ordering::Equal",
+                "core::cmp::Ordering::Greater",
+            ],
+        );
+        // Atomic orderings are not comparisons.
+        check_imported_replacements(
+            parse_quote! { use std::sync::atomic::Ordering; },
+            parse_quote! { -> Ordering },
+            &["Default::default()"],
+        );
+        check_replacements(parse_quote! { -> Ordering }, &[], &["Default::default()"]);
+    }
+
+    #[test]
+    fn local_type_shadows_known_type() {
+        let mut local_types = LocalTypes::default();
+        local_types.names.insert("Duration".to_owned());
+        local_types.with_default.insert("Duration".to_owned());
+        let context = ReplacementContext {
+            local_types: Arc::new(local_types),
+            ..Default::default()
+        };
+        assert_eq!(
+            return_type_replacements(
+                &parse_quote! { -> Duration },
+                &context,
+                &TypeParams::default()
+            )
+            .into_iter()
+            .map(|t| t.to_pretty_string())
+            .collect_vec(),
+            ["Default::default()"]
+        );
+        // Unless it's clearly the one from std.
+        assert_eq!(
+            return_type_replacements(
+                &parse_quote! { -> std::time::Duration },
+                &context,
+                &TypeParams::default()
+            )
+            .into_iter()
+            .map(|t| t.to_pretty_string())
+            .collect_vec(),
+            [
+                "std::time::Duration::ZERO",
+                "std::time::Duration::from_secs(1)",
+                "std::time::Duration::MAX"
+            ]
+        );
+    }
+
+    #[test]
+    fn local_type_without_default() {
+        let mut local_types = LocalTypes::default();
+        local_types.names.insert("S".to_owned());
+        let context = ReplacementContext {
+            local_types: Arc::new(local_types),
+            ..Default::default()
+        };
+        assert_eq!(
+            return_type_replacements(
+                &parse_quote! { -> Option<crate::S> },
+                &context,
+                &TypeParams::default()
+            )
+            .into_iter()
+            .map(|t| t.to_pretty_string())
+            .collect_vec(),
+            ["None"]
+        );
+    }
+
+    #[test]
+    fn boxed_error_replacement() {
+        check_replacements(
+            parse_quote! { -> Box<dyn std::error::Error + Send + Sync> },
+            &[],
+            &[r#""mutated".into()"#],
+        );
+    }
+
+    #[test]
+    fn range_replacement() {
+        check_replacements(
+            parse_quote! { -> Range<usize> },
+            &[],
+            &["0..0", "0..1", "1..0", "1..1"],
+        );
+    }
+
+    #[test]
+    fn bytes_and_json_replacements() {
+        check_imported_replacements(
+            parse_quote! { use bytes::Bytes; },
+            parse_quote! { -> Bytes },
+            &["Bytes::new()", r#"Bytes::from_static(b"xyzzy")"#],
+        );
+        check_replacements(
+            parse_quote! { -> serde_json::Value },
+            &[],
+            &["serde_json::Value::Null"],
+        );
+        // Some other Value is not matched.
+        check_replacements(
+            parse_quote! { -> toml::Value },
+            &[],
+            &["Default::default()"],
+        );
+    }
+
+    fn rule_replacements(rules: &[(&str, &[&str])], return_type: ReturnType) -> Vec<String> {
+        let context = ReplacementContext {
+            replacement_rules: rules
+                .iter()
+                .map(|(pattern, exprs)| {
+                    let exprs = exprs.iter().map(|e| e.to_string()).collect_vec();
+                    ReplacementRule::new(pattern, &exprs).unwrap()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        return_type_replacements(&return_type, &context, &TypeParams::default())
+            .into_iter()
+            .map(|t| t.to_pretty_string())
+            .collect_vec()
+    }
+
+    #[test]
+    fn configured_replacement_for_simple_type() {
+        let rules: &[(&str, &[&str])] = &[("UserId", &["UserId(1)", "UserId::MAX"])];
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> UserId }),
+            ["UserId(1)", "UserId::MAX"]
+        );
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> users::UserId }),
+            ["UserId(1)", "UserId::MAX"]
+        );
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> Option<UserId> }),
+            ["None", "Some(UserId(1))", "Some(UserId::MAX)"]
+        );
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> GroupId }),
+            ["Default::default()"]
+        );
+    }
+
+    #[test]
+    fn configured_replacement_with_placeholder() {
+        let rules: &[(&str, &[&str])] = &[
+            ("Id<T>", &["Id::new($T)"]),
+            ("MyResult<T>", &["MyResult::Ok($T)", "MyResult::Err(Oops)"]),
+            ("money::Money<u64>", &["Money::zero()"]),
+        ];
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> Id<bool> }),
+            ["Id::new(true)", "Id::new(false)"]
+        );
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> MyResult<Id<u8>> }),
+            [
+                "MyResult::Ok(Id::new(0))",
+                "MyResult::Ok(Id::new(1))",
+                "MyResult::Err(Oops)"
+            ]
+        );
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> crate::money::Money<u64> }),
+            ["Money::zero()"]
+        );
+        // The concrete argument doesn't match, and neither does a different module.
+        for return_type in [
+            parse_quote! { -> money::Money<u32> },
+            parse_quote! { -> cash::Money<u64> },
+        ] {
+            assert!(!rule_replacements(rules, return_type).contains(&"Money::zero()".to_owned()));
+        }
+    }
+
+    #[test]
+    fn placeholders_are_not_filled_inside_literals() {
+        let rules: &[(&str, &[&str])] = &[(
+            "Label<T>",
+            &[r#"Label::new($T, "$T costs $5")"#, "Label::sigil('$')"],
+        )];
+        assert_eq!(
+            rule_replacements(rules, parse_quote! { -> Label<bool> }),
+            [
+                r#"Label::new(true, "$T costs $5")"#,
+                r#"Label::new(false, "$T costs $5")"#,
+                "Label::sigil('$')"
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_replacement_rules() {
+        let exprs = |exprs: &[&str]| exprs.iter().map(|e| e.to_string()).collect_vec();
+        let err = ReplacementRule::new("Id<", &exprs(&["Id(1)"])).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Failed to parse replacement type pattern"),
+            "{err:#}"
+        );
+        let err = ReplacementRule::new("Id<T>", &exprs(&["Id::new($U)"])).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("uses $U, which is not a generic argument"),
+            "{err:#}"
+        );
+        let err = ReplacementRule::new("Id", &exprs(&["Id::new("])).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Failed to parse replacement expression"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn type_param_replacements() {
+        let mut generics: Generics =
+            parse_quote! { <T: FromStr, D: Default, N: num::Num, F: From<u8>, U> };
+        generics.where_clause = Some(parse_quote! { where U: Clone + Default, T: Debug });
+        let type_params = TypeParams::default().with_generics(&generics);
+        let context = ReplacementContext::default();
+        let reps = |return_type: ReturnType| {
+            return_type_replacements(&return_type, &context, &type_params)
+                .into_iter()
+                .map(|t| t.to_pretty_string())
+                .collect_vec()
+        };
+        assert_eq!(reps(parse_quote! { -> T }), Vec::<String>::new());
+        assert_eq!(reps(parse_quote! { -> Option<T> }), ["None"]);
+        assert_eq!(reps(parse_quote! { -> D }), ["Default::default()"]);
+        assert_eq!(reps(parse_quote! { -> N }), ["N::zero()", "N::one()"]);
+        assert_eq!(reps(parse_quote! { -> F }), ["F::from(0)", "F::from(1)"]);
+        assert_eq!(reps(parse_quote! { -> U }), ["Default::default()"]);
+        assert_eq!(
+            reps(parse_quote! { -> Result<(D, F)> }),
+            [
+                "Ok((Default::default(), F::from(0)))",
+                "Ok((Default::default(), F::from(1)))"
+            ]
+        );
+        // A type that's not a parameter is unaffected.
+        assert_eq!(reps(parse_quote! { -> V }), ["Default::default()"]);
+        // Inner parameters are added to the outer ones.
+        let inner = type_params.with_generics(&parse_quote! { <V: Zero> });
+        assert_eq!(
+            return_type_replacements(&parse_quote! { -> (V, D) }, &context, &inner)
+                .into_iter()
+                .map(|t| t.to_pretty_string())
+                .collect_vec(),
+            ["(V::zero(), Default::default())"]
+        );
+    }
+
     #[test]
     fn impl_matches_iterator() {
         assert_eq!(
@@ -780,9 +1960,124 @@ mod test {
         );
     }
 
+    #[test]
+    fn drop_error_replacements() {
+        let expr: Expr = parse_quote! { f() };
+        let context = ReplacementContext::default();
+        assert_eq!(
+            drop_error_replacement(
+                &parse_quote! { -> Result<bool> },
+                &expr,
+                &context,
+                &TypeParams::default()
+            )
+            .unwrap()
+            .to_pretty_string(),
+            "match f() {Ok(v) => v, Err(_) => return Ok(true)}"
+        );
+        assert_eq!(
+            drop_error_replacement(
+                &parse_quote! { -> Option<usize> },
+                &expr,
+                &context,
+                &TypeParams::default()
+            )
+            .unwrap()
+            .to_pretty_string(),
+            "match f() {Some(v) => v, None => return Some(0)}"
+        );
+        assert!(drop_error_replacement(
+            &parse_quote! { -> usize },
+            &expr,
+            &context,
+            &TypeParams::default()
+        )
+        .is_none());
+        assert!(drop_error_replacement(
+            &ReturnType::Default,
+            &expr,
+            &context,
+            &TypeParams::default()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn local_enum_replacements() {
+        let mut local_types = LocalTypes::default();
+        local_types.add_enum(
+            "Phase".to_owned(),
+            vec!["Build".to_owned(), "Test".to_owned()],
+        );
+        let mut context = ReplacementContext {
+            local_types: Arc::new(local_types),
+            ..Default::default()
+        };
+        fn reps(return_type: ReturnType, context: &ReplacementContext) -> Vec<String> {
+            return_type_replacements(&return_type, context, &TypeParams::default())
+                .into_iter()
+                .map(|t| t.to_pretty_string())
+                .collect_vec()
+        }
+        assert_eq!(
+            reps(parse_quote! { -> Phase }, &context),
+            ["Phase::Build", "Phase::Test"]
+        );
+        assert_eq!(
+            reps(parse_quote! { -> Option<crate::lab::Phase> }, &context),
+            [
+                "None",
+                "Some(crate::lab::Phase::Build)",
+                "Some(crate::lab::Phase::Test)"
+            ]
+        );
+        assert_eq!(
+            reps(parse_quote! { -> Other }, &context),
+            ["Default::default()"]
+        );
+        // An enum with the same name from some other crate.
+        assert_eq!(
+            reps(parse_quote! { -> build_tools::Phase }, &context),
+            ["Default::default()"]
+        );
+        // But this might be a module in the tree.
+        Arc::make_mut(&mut context.local_types)
+            .mods
+            .insert("lab".to_owned());
+        assert_eq!(
+            reps(parse_quote! { -> lab::Phase }, &context),
+            ["lab::Phase::Build", "lab::Phase::Test"]
+        );
+
+        // Another enum with the same name: only the common variants are used.
+        Arc::make_mut(&mut context.local_types).add_enum(
+            "Phase".to_owned(),
+            vec!["Test".to_owned(), "Check".to_owned()],
+        );
+        assert_eq!(reps(parse_quote! { -> Phase }, &context), ["Phase::Test"]);
+    }
+
     fn check_replacements(return_type: ReturnType, error_exprs: &[Expr], expected: &[&str]) {
+        let context = ReplacementContext {
+            error_exprs: error_exprs.to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(
+            return_type_replacements(&return_type, &context, &TypeParams::default())
+                .into_iter()
+                .map(|t| t.to_pretty_string())
+                .collect_vec(),
+            expected
+        );
+    }
+
+    fn check_imported_replacements(file: syn::File, return_type: ReturnType, expected: &[&str]) {
+        let context = ReplacementContext {
+            imports: Imports::in_file(&file),
+            ..Default::default()
+        };
         assert_eq!(
-            return_type_replacements(&return_type, error_exprs)
+            return_type_replacements(&return_type, &context, &TypeParams::default())
                 .into_iter()
                 .map(|t| t.to_pretty_string())
                 .collect_vec(),
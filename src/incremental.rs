@@ -0,0 +1,605 @@
+// Copyright 2024 Martin Pool
+
+//! Reuse outcomes from a previous run for mutants that haven't changed.
+//!
+//! Every mutant outcome is recorded in `outcomes.json` with a fingerprint: a hash of
+//! the mutated function, the test code of its package, the sources of the packages it
+//! depends on by path, `Cargo.lock`, and the options that control how it's built and
+//! tested. With `--incremental`, mutants whose fingerprint
+//! matches a caught, missed, or unviable mutant from the previous run are not tested
+//! again, and the previous outcome is copied into the new output.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::time::Duration;
+
+use anyhow::Context;
+use camino::{Utf8Path, Utf8PathBuf};
+use itertools::Itertools;
+use quote::ToTokens;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use syn::ext::IdentExt;
+use syn::{Attribute, Item};
+use tracing::{debug, warn};
+
+use crate::cargo::rustflags;
+use crate::mutate::Mutant;
+use crate::options::Options;
+use crate::outcome::{Phase, PhaseResult, ScenarioOutcome, SummaryOutcome};
+use crate::package::Package;
+use crate::process::ProcessStatus;
+use crate::scenario::Scenario;
+use crate::visit::{attr_is_cfg_test, attr_is_test};
+use crate::Result;
+
+/// Computes fingerprints of mutants, to recognize them across runs.
+#[derive(Debug, Default)]
+pub struct Fingerprints {
+    /// A hash of the test code and dependencies of each package, and of the workspace
+    /// inputs, by package name.
+    package_digests: HashMap<String, String>,
+}
+
+impl Fingerprints {
+    pub fn new(
+        workspace_dir: &Utf8Path,
+        packages: &[&Package],
+        options: &Options,
+    ) -> Result<Fingerprints> {
+        let mut workspace_hasher = Sha256::new();
+        let lock_path = workspace_dir.join("Cargo.lock");
+        if lock_path.is_file() {
+            workspace_hasher
+                .update(fs::read(&lock_path).with_context(|| format!("read {lock_path:?}"))?);
+        }
+        workspace_hasher.update(
+            format!(
+                "{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}",
+                options.additional_cargo_args,
+                options.additional_cargo_test_args,
+                options.features,
+                options.test_tool,
+                options.select_tests,
+                options.check_only,
+                options.schemata,
+                // Including the lints cap.
+                rustflags(),
+            )
+            .as_bytes(),
+        );
+        let mut package_digests = HashMap::new();
+        for package in packages {
+            let mut hasher = workspace_hasher.clone();
+            let package_dir = workspace_dir
+                .join(&package.relative_manifest_path)
+                .parent()
+                .expect("manifest has a parent directory")
+                .to_owned();
+            hash_test_sources(&mut hasher, &package_dir)?;
+            // Path dependencies are hashed completely, since any of their code might
+            // be called by the mutated function or its tests.
+            for dependency_dir in &package.path_dependency_dirs {
+                hasher.update(
+                    dependency_dir
+                        .strip_prefix(workspace_dir)
+                        .unwrap_or(dependency_dir)
+                        .as_str(),
+                );
+                hasher.update(b"\0");
+                for path in rust_sources(dependency_dir)? {
+                    hash_file(&mut hasher, dependency_dir, &path)?;
+                }
+            }
+            package_digests.insert(package.name.clone(), format!("{:x}", hasher.finalize()));
+        }
+        Ok(Fingerprints { package_digests })
+    }
+
+    /// Return the fingerprint of a mutant, or None if its package is unknown.
+    pub fn fingerprint(&self, mutant: &Mutant) -> Option<String> {
+        let package_digest = self.package_digests.get(mutant.package_name())?;
+        let mut hasher = Sha256::new();
+        hasher.update(package_digest.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(mutant.name(false, false).as_bytes());
+        hasher.update(b"\0");
+        hasher.update(mutated_function_text(mutant).as_bytes());
+        Some(format!("{:x}", hasher.finalize()))
+    }
+}
+
+/// Return the text of the function containing a mutant, with the mutant applied.
+///
+/// For mutants outside of any function, this is just the original and replacement text.
+fn mutated_function_text(mutant: &Mutant) -> String {
+    match &mutant.function {
+        Some(function) if function.span.contains(&mutant.span) => {
+            let function_text = function.span.extract(mutant.source_file.code());
+            mutant
+                .span
+                .relative_to(function.span.start)
+                .replace(&function_text, &mutant.replacement)
+        }
+        _ => format!("{}\0{}", mutant.original_text(), mutant.replacement),
+    }
+}
+
+/// Hash the test code of a package: everything under its `tests` directory, and in its
+/// other sources, items marked `#[test]` or `#[cfg(test)]` and the files of
+/// `#[cfg(test)]` modules.
+///
+/// The rest of the package is left out, so that changing one function doesn't cause
+/// the mutants in others to be tested again.
+fn hash_test_sources(hasher: &mut Sha256, package_dir: &Utf8Path) -> Result<()> {
+    let mut sources = Vec::new();
+    for path in rust_sources(package_dir)? {
+        let code = fs::read_to_string(&path).with_context(|| format!("read {path:?}"))?;
+        let syn_file = syn::parse_file(&code).ok();
+        sources.push((path, syn_file));
+    }
+    let mut test_files: HashSet<Utf8PathBuf> = sources
+        .iter()
+        .filter(|(path, syn_file)| {
+            // Files that can't be parsed are hashed completely, to be safe.
+            syn_file.is_none()
+                || path
+                    .strip_prefix(package_dir)
+                    .is_ok_and(|path| path.starts_with("tests"))
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    // Test modules can declare more modules in other files, so repeat until no more
+    // are found.
+    loop {
+        let n_test_files = test_files.len();
+        for (path, syn_file) in &sources {
+            if let Some(syn_file) = syn_file {
+                let in_test =
+                    test_files.contains(path) || syn_file.attrs.iter().any(attr_is_cfg_test);
+                if in_test {
+                    test_files.insert(path.clone());
+                }
+                find_test_mod_files(
+                    path,
+                    &mod_dirs(path),
+                    &syn_file.items,
+                    in_test,
+                    &mut test_files,
+                );
+            }
+        }
+        if test_files.len() == n_test_files {
+            break;
+        }
+    }
+    for (path, syn_file) in &sources {
+        if test_files.contains(path) {
+            hash_file(hasher, package_dir, path)?;
+        } else if let Some(syn_file) = syn_file {
+            hasher.update(path.strip_prefix(package_dir).unwrap_or(path).as_str());
+            hasher.update(b"\0");
+            hash_test_items(hasher, &syn_file.items);
+        }
+    }
+    Ok(())
+}
+
+/// Hash the text of items marked as tests, including those inside inline modules.
+fn hash_test_items(hasher: &mut Sha256, items: &[Item]) {
+    for item in items {
+        if item_attrs(item)
+            .iter()
+            .any(|attr| attr_is_cfg_test(attr) || attr_is_test(attr))
+        {
+            hasher.update(item.to_token_stream().to_string());
+            hasher.update(b"\0");
+        } else if let Item::Mod(syn::ItemMod {
+            content: Some((_, items)),
+            ..
+        }) = item
+        {
+            hash_test_items(hasher, items);
+        }
+    }
+}
+
+/// Find the files of modules declared inside test code.
+///
+/// `mod_dirs` are the directories that might contain the files of modules declared
+/// in `items`.
+fn find_test_mod_files(
+    path: &Utf8Path,
+    mod_dirs: &[Utf8PathBuf],
+    items: &[Item],
+    in_test: bool,
+    test_files: &mut HashSet<Utf8PathBuf>,
+) {
+    for item in items {
+        let Item::Mod(item_mod) = item else {
+            continue;
+        };
+        let in_test = in_test || item_mod.attrs.iter().any(attr_is_cfg_test);
+        let name = item_mod.ident.unraw().to_string();
+        if let Some((_, items)) = &item_mod.content {
+            let mod_dirs = mod_dirs.iter().map(|dir| dir.join(&name)).collect_vec();
+            find_test_mod_files(path, &mod_dirs, items, in_test, test_files);
+        } else if in_test {
+            if let Some(path_attr) = mod_path_attr(&item_mod.attrs) {
+                let dir = path.parent().expect("source file has a parent directory");
+                test_files.insert(dir.join(path_attr));
+            }
+            for dir in mod_dirs {
+                test_files.insert(dir.join(format!("{name}.rs")));
+                test_files.insert(dir.join(&name).join("mod.rs"));
+            }
+        }
+    }
+}
+
+/// The directories that might contain the files of modules declared in a source file.
+///
+/// For `lib.rs`, `main.rs`, `mod.rs`, and other crate roots, this is the file's own
+/// directory; for other files it's a subdirectory named after the file. Both are
+/// returned, since crate roots can't be told apart from the file alone.
+fn mod_dirs(path: &Utf8Path) -> Vec<Utf8PathBuf> {
+    let dir = path.parent().expect("source file has a parent directory");
+    match path.file_stem() {
+        Some("lib" | "main" | "mod") | None => vec![dir.to_owned()],
+        Some(stem) => vec![dir.to_owned(), dir.join(stem)],
+    }
+}
+
+/// Return the value of a `#[path = "..."]` attribute.
+fn mod_path_attr(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match &attr.meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            path,
+            value:
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(lit),
+                    ..
+                }),
+            ..
+        }) if path.is_ident("path") => Some(lit.value()),
+        _ => None,
+    })
+}
+
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(item) => &item.attrs,
+        Item::Enum(item) => &item.attrs,
+        Item::ExternCrate(item) => &item.attrs,
+        Item::Fn(item) => &item.attrs,
+        Item::ForeignMod(item) => &item.attrs,
+        Item::Impl(item) => &item.attrs,
+        Item::Macro(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        Item::Static(item) => &item.attrs,
+        Item::Struct(item) => &item.attrs,
+        Item::Trait(item) => &item.attrs,
+        Item::TraitAlias(item) => &item.attrs,
+        Item::Type(item) => &item.attrs,
+        Item::Union(item) => &item.attrs,
+        Item::Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/// Hash the path of a file relative to a directory, and its contents.
+fn hash_file(hasher: &mut Sha256, dir: &Utf8Path, path: &Utf8Path) -> Result<()> {
+    hasher.update(path.strip_prefix(dir).unwrap_or(path).as_str());
+    hasher.update(b"\0");
+    hasher.update(fs::read(path).with_context(|| format!("read {path:?}"))?);
+    hasher.update(b"\0");
+    Ok(())
+}
+
+/// Find all the Rust sources in a package directory, recursively, in a stable order.
+///
+/// Build and output directories are skipped.
+fn rust_sources(dir: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    let mut paths = Vec::new();
+    if !dir.is_dir() {
+        return Ok(paths);
+    }
+    for entry in dir
+        .read_dir_utf8()
+        .with_context(|| format!("read directory {dir:?}"))?
+    {
+        let entry = entry?;
+        let path = entry.path().to_owned();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            if name != "target" && !name.starts_with("mutants.out") && !name.starts_with('.') {
+                paths.extend(rust_sources(&path)?);
+            }
+        } else if file_type.is_file() && path.extension() == Some("rs") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Outcomes read from the `outcomes.json` of a previous run.
+#[derive(Debug, Default)]
+pub struct PreviousOutcomes {
+    /// The previous output directory, like `mutants.out.old`.
+    dir: Utf8PathBuf,
+    by_fingerprint: HashMap<String, PreviousOutcome>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviousLabOutcome {
+    outcomes: Vec<PreviousOutcome>,
+}
+
+/// Read all the outcomes from an `outcomes.json` file.
+pub fn read_outcomes(path: &Utf8Path) -> Result<Vec<PreviousOutcome>> {
+    let json = fs::read_to_string(path).with_context(|| format!("read {path:?}"))?;
+    let lab_outcome: PreviousLabOutcome =
+        serde_json::from_str(&json).with_context(|| format!("parse {path:?}"))?;
+    Ok(lab_outcome.outcomes)
+}
+
+/// The parts of a [ScenarioOutcome] from a previous run that are needed to reuse it.
+#[derive(Debug, Deserialize)]
+pub struct PreviousOutcome {
+    /// The scenario, as serialized: either `"Baseline"` or `{"Mutant": {...}}`.
+    scenario: serde_json::Value,
+    log_path: Utf8PathBuf,
+    summary: SummaryOutcome,
+    phase_results: Vec<PreviousPhaseResult>,
+    #[serde(default)]
+    fingerprint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviousPhaseResult {
+    phase: Phase,
+    duration: f64,
+    process_status: ProcessStatus,
+    argv: Vec<String>,
+}
+
+impl PreviousOutcomes {
+    /// Read outcomes from a previous output directory.
+    ///
+    /// If there are no previous outcomes, or they can't be read, this warns and returns
+    /// no outcomes, so that every mutant will be tested.
+    pub fn read(dir: &Utf8Path) -> PreviousOutcomes {
+        let outcomes = match read_outcomes(&dir.join("outcomes.json")) {
+            Ok(outcomes) => outcomes,
+            Err(err) => {
+                warn!("Can't reuse previous outcomes: {err:#}");
+                return PreviousOutcomes::default();
+            }
+        };
+        let by_fingerprint: HashMap<String, PreviousOutcome> = outcomes
+            .into_iter()
+            .filter(|outcome| {
+                matches!(
+                    outcome.summary,
+                    SummaryOutcome::CaughtMutant
+                        | SummaryOutcome::MissedMutant
+                        | SummaryOutcome::Unviable
+                )
+            })
+            .filter_map(|outcome| Some((outcome.fingerprint.clone()?, outcome)))
+            .collect();
+        debug!(n = by_fingerprint.len(), "Read reusable previous outcomes");
+        PreviousOutcomes {
+            dir: dir.to_owned(),
+            by_fingerprint,
+        }
+    }
+
+    /// Return the previous outcome for a mutant with this fingerprint, if it can be reused.
+    ///
+    /// Only caught, missed, and unviable outcomes are reused: timeouts and other failures
+    /// might not happen again.
+    pub fn get(&self, fingerprint: &str) -> Option<&PreviousOutcome> {
+        self.by_fingerprint.get(fingerprint)
+    }
+
+    /// Return the path of the log from a previous outcome.
+    pub fn log_path(&self, outcome: &PreviousOutcome) -> Option<Utf8PathBuf> {
+        // The directory has been renamed since the log was written, so look for it in its
+        // new location.
+        Some(self.dir.join("log").join(outcome.log_path.file_name()?))
+    }
+}
+
+impl PreviousOutcome {
+    /// Return the serialized mutant, if this is the outcome of a mutant.
+    pub fn mutant_json(&self) -> Option<&serde_json::Value> {
+        self.scenario.get("Mutant")
+    }
+
+    /// Convert to an outcome for a scenario, keeping the same log file.
+    pub fn into_outcome(self, scenario: Scenario) -> ScenarioOutcome {
+        let phase_results = self.phase_results();
+        ScenarioOutcome::previous(self.log_path, scenario, phase_results, self.fingerprint)
+    }
+
+    pub fn phase_results(&self) -> Vec<PhaseResult> {
+        self.phase_results
+            .iter()
+            .map(|pr| PhaseResult {
+                phase: pr.phase,
+                duration: Duration::from_secs_f64(pr.duration),
+                process_status: pr.process_status,
+                argv: pr.argv.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::console::Console;
+    use crate::workspace::{PackageFilter, Workspace};
+
+    #[test]
+    fn mutated_function_text_of_mutants() {
+        let workspace = Workspace::open("testdata/integration_tests").unwrap();
+        let mutants = workspace
+            .mutants(&PackageFilter::All, &Options::default(), &Console::new())
+            .unwrap();
+        assert_eq!(
+            mutants.iter().map(mutated_function_text).collect_vec(),
+            [
+                "pub fn double(n: u32) -> u32 {\n    0\n}",
+                "pub fn double(n: u32) -> u32 {\n    1\n}",
+                "pub fn double(n: u32) -> u32 {\n    2 + n\n}",
+                "pub fn double(n: u32) -> u32 {\n    2 / n\n}",
+            ]
+        );
+    }
+
+    #[test]
+    fn fingerprints_depend_on_mutant_and_sources() {
+        let workspace = Workspace::open("testdata/integration_tests").unwrap();
+        let options = Options::default();
+        let mutants = workspace
+            .mutants(&PackageFilter::All, &options, &Console::new())
+            .unwrap();
+        let packages = mutants.iter().map(|m| m.package()).unique().collect_vec();
+        let fingerprints = Fingerprints::new(
+            Utf8Path::new("testdata/integration_tests"),
+            &packages,
+            &options,
+        )
+        .unwrap();
+        let first = mutants
+            .iter()
+            .map(|m| fingerprints.fingerprint(m).unwrap())
+            .collect_vec();
+        assert_eq!(first.iter().unique().count(), 4);
+        assert!(first.iter().all(|f| f.len() == 64));
+
+        // Different test arguments give different fingerprints.
+        let options = Options {
+            additional_cargo_test_args: vec!["--release".to_owned()],
+            ..Options::default()
+        };
+        let fingerprints = Fingerprints::new(
+            Utf8Path::new("testdata/integration_tests"),
+            &packages,
+            &options,
+        )
+        .unwrap();
+        assert_ne!(fingerprints.fingerprint(&mutants[0]).unwrap(), first[0]);
+
+        // And so does building mutants together.
+        let options = Options {
+            schemata: true,
+            ..Options::default()
+        };
+        let fingerprints = Fingerprints::new(
+            Utf8Path::new("testdata/integration_tests"),
+            &packages,
+            &options,
+        )
+        .unwrap();
+        assert_ne!(fingerprints.fingerprint(&mutants[0]).unwrap(), first[0]);
+    }
+
+    #[test]
+    fn test_sources_digest_depends_only_on_test_code() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = Utf8Path::from_path(tmp.path()).unwrap();
+        fs::create_dir_all(dir.join("src/parse")).unwrap();
+        fs::create_dir_all(dir.join("tests")).unwrap();
+        let write = |path: &str, code: &str| fs::write(dir.join(path), code).unwrap();
+        let digest = || {
+            let mut hasher = Sha256::new();
+            hash_test_sources(&mut hasher, dir).unwrap();
+            format!("{:x}", hasher.finalize())
+        };
+        write(
+            "src/lib.rs",
+            "pub fn f() -> u32 { 1 }\nmod parse;\n#[cfg(test)]\nmod test { #[test] fn t() {} }\n",
+        );
+        write(
+            "src/parse.rs",
+            "pub fn g() {}\n#[cfg(test)]\nmod tests;\n#[test]\nfn top() {}\n",
+        );
+        write("src/parse/tests.rs", "fn helper() -> u32 { 2 }\n");
+        write("tests/api.rs", "#[test]\nfn api() {}\n");
+        let original = digest();
+
+        // Changing code that isn't part of any test leaves it the same.
+        write(
+            "src/lib.rs",
+            "pub fn f() -> u32 { 2 }\nmod parse;\n#[cfg(test)]\nmod test { #[test] fn t() {} }\n",
+        );
+        write(
+            "src/parse.rs",
+            "pub fn g() { f(); }\n#[cfg(test)]\nmod tests;\n#[test]\nfn top() {}\n",
+        );
+        assert_eq!(digest(), original);
+
+        // Changing the tests, in any of these places, changes it.
+        let mut digests = vec![original];
+        write(
+            "src/lib.rs",
+            "pub fn f() -> u32 { 2 }\nmod parse;\n#[cfg(test)]\nmod test { #[test] fn u() {} }\n",
+        );
+        digests.push(digest());
+        write(
+            "src/parse.rs",
+            "pub fn g() { f(); }\n#[cfg(test)]\nmod tests;\n#[test]\nfn other() {}\n",
+        );
+        digests.push(digest());
+        write("src/parse/tests.rs", "fn helper() -> u32 { 3 }\n");
+        digests.push(digest());
+        write("tests/api.rs", "#[test]\nfn api2() {}\n");
+        digests.push(digest());
+        assert_eq!(digests.iter().unique().count(), digests.len());
+    }
+
+    #[test]
+    fn fingerprints_depend_on_path_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = Utf8Path::from_path(tmp.path()).unwrap();
+        fs::create_dir_all(dir.join("main/src")).unwrap();
+        fs::create_dir_all(dir.join("utils/src")).unwrap();
+        fs::write(dir.join("utils/src/lib.rs"), "pub fn one() -> u32 { 1 }\n").unwrap();
+        let package = Package {
+            name: "main".to_owned(),
+            relative_manifest_path: "main/Cargo.toml".into(),
+            path_dependency_dirs: vec![dir.join("utils")],
+        };
+        let digest = || {
+            Fingerprints::new(dir, &[&package], &Options::default())
+                .unwrap()
+                .package_digests["main"]
+                .clone()
+        };
+        let original = digest();
+        assert_eq!(digest(), original);
+        fs::write(dir.join("utils/src/lib.rs"), "pub fn one() -> u32 { 2 }\n").unwrap();
+        assert_ne!(digest(), original);
+    }
+
+    #[test]
+    fn find_rust_sources() {
+        let dir = Utf8Path::new("testdata/integration_tests");
+        assert_eq!(
+            rust_sources(dir).unwrap(),
+            [dir.join("src/lib.rs"), dir.join("tests/api.rs")]
+        );
+        assert_eq!(
+            rust_sources(&dir.join("nonexistent")).unwrap(),
+            [] as [Utf8PathBuf; 0]
+        );
+    }
+}
@@ -3,8 +3,11 @@
 //! Successively apply mutations to the source code and run cargo to check, build, and test them.
 
 use std::cmp::{max, min};
+use std::fs::read_to_string;
+use std::io::Write;
+use std::iter;
 use std::panic::resume_unwind;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -14,9 +17,15 @@ use tracing::warn;
 use tracing::{debug, debug_span, error, info, trace};
 
 use crate::cargo::run_cargo;
-use crate::outcome::LabOutcome;
+use crate::console::plural;
+use crate::coverage::{partition_uncovered, LlvmTools, TestCoverage};
+use crate::incremental::{Fingerprints, PreviousOutcomes};
+use crate::lcov::LineCoverage;
+use crate::outcome::{LabOutcome, PhaseResult};
 use crate::output::OutputDir;
 use crate::package::Package;
+use crate::process::ProcessStatus;
+use crate::schemata::{schemata, ACTIVE_MUTANT_ENV};
 use crate::*;
 
 /// Run all possible mutation experiments.
@@ -37,20 +46,96 @@ pub fn test_mutants(
         .output_in_dir
         .as_ref()
         .map_or(workspace_dir, |p| p.as_path());
-    let output_dir = OutputDir::new(output_in_dir)?;
+    let existing_output_dir = if options.resume {
+        let existing = OutputDir::open_existing(output_in_dir)?;
+        if existing.is_none() {
+            warn!("No previous output to resume; testing all mutants");
+        }
+        existing
+    } else {
+        None
+    };
+    let resuming = existing_output_dir.is_some();
+    let mut output_dir = match existing_output_dir {
+        Some(output_dir) => output_dir,
+        None => OutputDir::new(output_in_dir)?,
+    };
     console.set_debug_log(output_dir.open_debug_log()?);
 
-    if options.shuffle {
-        fastrand::shuffle(&mut mutants);
+    if !resuming {
+        if options.shuffle {
+            fastrand::shuffle(&mut mutants);
+        }
+        output_dir.write_mutants_list(&mutants)?;
     }
-    output_dir.write_mutants_list(&mutants)?;
     console.discovered_mutants(&mutants);
     if mutants.is_empty() {
         warn!("No mutants found under the active filters");
         return Ok(LabOutcome::default());
     }
+    if resuming {
+        let n_mutants = mutants.len();
+        mutants = output_dir.resume(mutants)?;
+        info!(
+            "Resuming: {} of {n_mutants} mutants were already tested",
+            n_mutants - mutants.len()
+        );
+        if mutants.is_empty() {
+            return Ok(finish_lab(
+                Mutex::new(output_dir),
+                start_time,
+                &options,
+                console,
+            ));
+        }
+    }
+    if let Some(coverage_report) = &options.coverage_report {
+        let line_coverage = LineCoverage::parse(
+            &read_to_string(coverage_report)
+                .with_context(|| format!("read coverage report {coverage_report:?}"))?,
+        );
+        let uncovered;
+        (mutants, uncovered) = partition_uncovered(mutants, &line_coverage, workspace_dir);
+        output_dir.write_uncovered_list(&uncovered)?;
+        if !uncovered.is_empty() {
+            info!(
+                "Skipping {} in code not covered by {coverage_report}; see uncovered.txt",
+                plural(uncovered.len(), "mutant")
+            );
+        }
+        if mutants.is_empty() {
+            if output_dir.lab_outcome.total_mutants > 0 {
+                // Some were tested before resuming.
+                return Ok(finish_lab(
+                    Mutex::new(output_dir),
+                    start_time,
+                    &options,
+                    console,
+                ));
+            }
+            return Ok(output_dir.take_lab_outcome());
+        }
+    }
     let all_packages = mutants.iter().map(|m| m.package()).unique().collect_vec();
     debug!(?all_packages);
+    output_dir.set_fingerprints(Fingerprints::new(workspace_dir, &all_packages, &options)?);
+    let previous_outcomes = if options.incremental {
+        match output_dir.previous_path() {
+            Some(previous_path) => Some(PreviousOutcomes::read(&previous_path)),
+            None => {
+                warn!("No previous output to reuse; testing all mutants");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let llvm_tools = if options.select_tests && !options.check_only {
+        Some(LlvmTools::find(workspace_dir)?)
+    } else {
+        None
+    };
 
     let output_mutex = Mutex::new(output_dir);
     let build_dir = if options.in_place {
@@ -69,6 +154,7 @@ pub fn test_mutants(
                     test: options.test_timeout.unwrap_or(Duration::MAX),
                     build: options.build_timeout.unwrap_or(Duration::MAX),
                 },
+                None,
                 &options,
                 console,
             )?;
@@ -88,7 +174,6 @@ pub fn test_mutants(
         }
         BaselineStrategy::Skip => None,
     };
-    let mut build_dirs = vec![build_dir];
 
     let baseline_duration_by_phase = |phase| {
         baseline_outcome
@@ -101,7 +186,58 @@ pub fn test_mutants(
         test: test_timeout(baseline_duration_by_phase(Phase::Test), &options),
     };
 
+    let test_coverage = if let Some(llvm_tools) = &llvm_tools {
+        let mut log_file = output_mutex
+            .lock()
+            .expect("lock output_dir to create log")
+            .create_coverage_log()?;
+        let test_coverage = TestCoverage::measure(
+            &build_dir,
+            llvm_tools,
+            &all_packages,
+            timeouts.build,
+            timeouts.test,
+            &mut log_file,
+            &options,
+            console,
+        )?;
+        debug!(tests = test_coverage.test_count(), "Measured test coverage");
+        Some(test_coverage)
+    } else {
+        None
+    };
+
+    console.start_testing_mutants(mutants.len());
+    if let Some(previous_outcomes) = &previous_outcomes {
+        mutants = reuse_outcomes(mutants, previous_outcomes, &output_mutex, &options, console)?;
+        if mutants.is_empty() {
+            return Ok(finish_lab(output_mutex, start_time, &options, console));
+        }
+    }
+    if let Some(test_coverage) = &test_coverage {
+        mutants = miss_untested_mutants(mutants, test_coverage, &output_mutex, &options, console)?;
+        if mutants.is_empty() {
+            return Ok(finish_lab(output_mutex, start_time, &options, console));
+        }
+    }
+    if options.schemata && !options.check_only {
+        mutants = test_schemata(
+            mutants,
+            &build_dir,
+            &output_mutex,
+            timeouts,
+            test_coverage.as_ref(),
+            &options,
+            console,
+        )?;
+        if mutants.is_empty() {
+            return Ok(finish_lab(output_mutex, start_time, &options, console));
+        }
+    }
+
     let jobs = max(1, min(options.jobs.unwrap_or(1), mutants.len()));
+    let mut build_dirs = vec![build_dir];
+    // TODO: Maybe, make the copies in parallel on each thread, rather than up front?
     for i in 1..jobs {
         debug!("copy build dir {i}");
         build_dirs.push(BuildDir::copy_from(
@@ -113,40 +249,56 @@ pub fn test_mutants(
     }
     debug!(build_dirs = ?build_dirs);
 
-    // Create n threads, each dedicated to one build directory. Each of them tries to take a
-    // scenario to test off the queue, and then exits when there are no more left.
-    console.start_testing_mutants(mutants.len());
-    let pending = Mutex::new(mutants.into_iter());
+    in_parallel(
+        &build_dirs.iter().collect_vec(),
+        mutants,
+        |build_dir, mutant| {
+            let _span = debug_span!("mutant", name = mutant.name(false, false)).entered();
+            let package = mutant.package().clone();
+            test_scenario(
+                build_dir,
+                &output_mutex,
+                &Scenario::Mutant(mutant),
+                &[&package],
+                timeouts,
+                test_coverage.as_ref(),
+                &options,
+                console,
+            )
+            .map(|_| ())
+        },
+    )?;
+
+    Ok(finish_lab(output_mutex, start_time, &options, console))
+}
+
+/// Do some work in parallel, with one thread dedicated to each build directory.
+///
+/// Each thread takes the next item off the queue, and then exits when there are no
+/// more left.
+fn in_parallel<T, W, F>(build_dirs: &[&BuildDir], work: W, f: F) -> Result<()>
+where
+    W: IntoIterator<Item = T>,
+    W::IntoIter: Send,
+    F: Fn(&BuildDir, T) -> Result<()> + Sync,
+{
+    let pending = &Mutex::new(work.into_iter());
+    let f = &f;
     thread::scope(|scope| -> crate::Result<()> {
         let mut threads = Vec::new();
-        // TODO: Maybe, make the copies in parallel on each thread, rather than up front?
-        for build_dir in build_dirs {
-            threads.push(scope.spawn(|| -> crate::Result<()> {
-                let build_dir = build_dir; // move it into this thread
+        for &build_dir in build_dirs {
+            threads.push(scope.spawn(move || -> crate::Result<()> {
                 trace!(thread_id = ?thread::current().id(), ?build_dir, "start thread");
                 loop {
-                    // Extract the mutant in a separate statement so that we don't hold the
-                    // lock while testing it.
+                    // Extract the item in a separate statement so that we don't hold the
+                    // lock while working on it.
                     let next = pending.lock().map(|mut s| s.next());
                     match next {
                         Err(err) => {
                             // PoisonError is not Send so we can't pass it directly.
                             return Err(anyhow!("Lock pending work queue: {}", err));
                         }
-                        Ok(Some(mutant)) => {
-                            let _span =
-                                debug_span!("mutant", name = mutant.name(false, false)).entered();
-                            let package = mutant.package().clone();
-                            test_scenario(
-                                &build_dir,
-                                &output_mutex,
-                                &Scenario::Mutant(mutant),
-                                &[&package],
-                                timeouts,
-                                &options,
-                                console,
-                            )?;
-                        }
+                        Ok(Some(item)) => f(build_dir, item)?,
                         Ok(None) => {
                             trace!("no more work");
                             return Ok(());
@@ -181,12 +333,20 @@ pub fn test_mutants(
         } else {
             Ok(())
         }
-    })?;
+    })
+}
 
+/// Report the overall outcome after all mutants have been tested.
+fn finish_lab(
+    output_mutex: Mutex<OutputDir>,
+    start_time: Instant,
+    options: &Options,
+    console: &Console,
+) -> LabOutcome {
     let output_dir = output_mutex
         .into_inner()
         .expect("final unlock mutants queue");
-    console.lab_finished(&output_dir.lab_outcome, start_time, &options);
+    console.lab_finished(&output_dir.lab_outcome, start_time, options);
     let lab_outcome = output_dir.take_lab_outcome();
     if lab_outcome.total_mutants == 0 {
         // This should be unreachable as we also bail out before copying
@@ -195,7 +355,254 @@ pub fn test_mutants(
     } else if lab_outcome.unviable == lab_outcome.total_mutants {
         warn!("No mutants were viable; perhaps there is a problem with building in a scratch directory");
     }
-    Ok(lab_outcome)
+    lab_outcome
+}
+
+/// Record outcomes from the previous run for mutants that haven't changed since then.
+///
+/// Returns the mutants that still need to be tested.
+fn reuse_outcomes(
+    mutants: Vec<Mutant>,
+    previous_outcomes: &PreviousOutcomes,
+    output_mutex: &Mutex<OutputDir>,
+    options: &Options,
+    console: &Console,
+) -> Result<Vec<Mutant>> {
+    let mut output_dir = output_mutex
+        .lock()
+        .expect("lock output dir to reuse outcomes");
+    let mut remaining = Vec::new();
+    for mutant in mutants {
+        let Some(previous_outcome) = output_dir
+            .fingerprint(&mutant)
+            .and_then(|fingerprint| previous_outcomes.get(&fingerprint))
+        else {
+            remaining.push(mutant);
+            continue;
+        };
+        let scenario = Scenario::Mutant(mutant);
+        let mut log_file = output_dir.create_log(&scenario)?;
+        log_file.message(&format!("{scenario}: outcome reused from the previous run"));
+        if let Some(previous_log_path) = previous_outcomes.log_path(previous_outcome) {
+            match read_to_string(&previous_log_path) {
+                Ok(previous_log) => log_file
+                    .open_append()?
+                    .write_all(previous_log.as_bytes())
+                    .context("copy previous log")?,
+                Err(err) => warn!("Failed to read previous log {previous_log_path:?}: {err}"),
+            }
+        }
+        let mut outcome = ScenarioOutcome::new(&log_file, scenario.clone());
+        for phase_result in previous_outcome.phase_results() {
+            outcome.add_phase_result(phase_result);
+        }
+        outcome.reused = true;
+        output_dir.add_scenario_outcome(&outcome)?;
+        console.scenario_finished(&scenario, &outcome, options);
+    }
+    debug!(remaining = remaining.len(), "Reused previous outcomes");
+    Ok(remaining)
+}
+
+/// Record mutants in functions that aren't executed by any test as missed, without
+/// building or testing them.
+///
+/// Returns the mutants that are covered by some tests.
+fn miss_untested_mutants(
+    mutants: Vec<Mutant>,
+    test_coverage: &TestCoverage,
+    output_mutex: &Mutex<OutputDir>,
+    options: &Options,
+    console: &Console,
+) -> Result<Vec<Mutant>> {
+    let mut output_dir = output_mutex
+        .lock()
+        .expect("lock output dir to record untested mutants");
+    let (covered, untested): (Vec<Mutant>, Vec<Mutant>) = mutants.into_iter().partition(|mutant| {
+        test_coverage
+            .covering_tests(mutant)
+            .map_or(true, |tests| !tests.is_empty())
+    });
+    for mutant in untested {
+        let scenario = Scenario::Mutant(mutant);
+        let mut log_file = output_dir.create_log(&scenario)?;
+        log_file.message(&format!(
+            "{scenario}: no tests execute this code, so the mutant is missed without being tested"
+        ));
+        let mut outcome = ScenarioOutcome::new(&log_file, scenario.clone());
+        outcome.add_phase_result(PhaseResult {
+            phase: Phase::Test,
+            duration: Duration::ZERO,
+            process_status: ProcessStatus::Success,
+            argv: Vec::new(),
+        });
+        output_dir.add_scenario_outcome(&outcome)?;
+        console.scenario_finished(&scenario, &outcome, options);
+    }
+    debug!(covered = covered.len(), "Recorded untested mutants");
+    Ok(covered)
+}
+
+/// Build the mutants of each package together, and then test each of them.
+///
+/// Functions whose mutants fail to build together are removed from the combined build
+/// and it's retried.
+///
+/// After a successful build, the build directory is copied for each of the other jobs,
+/// so that the mutants can be tested in parallel without being built again.
+///
+/// Returns the mutants that could not be tested this way, which should then be tested
+/// one at a time.
+fn test_schemata(
+    mutants: Vec<Mutant>,
+    build_dir: &BuildDir,
+    output_mutex: &Mutex<OutputDir>,
+    timeouts: Timeouts,
+    test_coverage: Option<&TestCoverage>,
+    options: &Options,
+    console: &Console,
+) -> Result<Vec<Mutant>> {
+    let (all_schemata, mut remaining) = schemata(mutants);
+    for mut schemata in all_schemata {
+        let package = Arc::clone(&schemata.package);
+        let mut log_file = output_mutex
+            .lock()
+            .expect("lock output_dir to create log")
+            .create_schemata_log(&package.name)?;
+        let build_result = loop {
+            if schemata.is_empty() {
+                break None;
+            }
+            log_file.message(&format!(
+                "build {} mutants of {} together",
+                schemata.mutants().count(),
+                package.name
+            ));
+            let applied = schemata.apply(build_dir)?;
+            let log_start = read_to_string(log_file.path())?.len();
+            let phase_result = run_cargo(
+                build_dir,
+                Some(&[&package]),
+                Phase::Build,
+                timeouts.build,
+                &mut log_file,
+                &[],
+                None,
+                options,
+                console,
+            )?;
+            if phase_result.is_success() {
+                break Some((applied, phase_result));
+            }
+            let failed = if phase_result.process_status.is_timeout() {
+                Vec::new()
+            } else {
+                applied.groups_with_errors(&read_to_string(log_file.path())?[log_start..])
+            };
+            drop(applied);
+            if failed.is_empty() {
+                debug!(
+                    package = package.name,
+                    "Combined build failed; testing mutants separately"
+                );
+                break None;
+            }
+            debug!(
+                package = package.name,
+                ?failed,
+                "Removing functions that failed to build"
+            );
+            remaining.extend(schemata.remove_groups(&failed));
+        };
+        let Some((applied, build_result)) = build_result else {
+            remaining.extend(schemata.into_mutants());
+            continue;
+        };
+        let jobs = max(
+            1,
+            min(options.jobs.unwrap_or(1), schemata.mutants().count()),
+        );
+        let mut build_dirs = Vec::new();
+        for i in 1..jobs {
+            debug!("copy built schemata build dir {i}");
+            build_dirs.push(build_dir.copy_built(options.leak_dirs, console)?);
+        }
+        let build_dirs = iter::once(build_dir).chain(&build_dirs).collect_vec();
+        in_parallel(
+            &build_dirs,
+            schemata.mutants(),
+            |build_dir, (id, mutant)| {
+                test_schemata_mutant(
+                    *id,
+                    mutant,
+                    &build_result,
+                    build_dir,
+                    output_mutex,
+                    timeouts,
+                    test_coverage,
+                    options,
+                    console,
+                )
+            },
+        )?;
+        drop(applied);
+    }
+    Ok(remaining)
+}
+
+/// Run the tests for one mutant in a tree that has already been built with schemata.
+#[allow(clippy::too_many_arguments)]
+fn test_schemata_mutant(
+    id: usize,
+    mutant: &Mutant,
+    build_result: &PhaseResult,
+    build_dir: &BuildDir,
+    output_mutex: &Mutex<OutputDir>,
+    timeouts: Timeouts,
+    test_coverage: Option<&TestCoverage>,
+    options: &Options,
+    console: &Console,
+) -> Result<()> {
+    let _span = debug_span!("mutant", name = mutant.name(false, false)).entered();
+    let scenario = Scenario::Mutant(mutant.clone());
+    let mut log_file = output_mutex
+        .lock()
+        .expect("lock output_dir to create log")
+        .create_log(&scenario)?;
+    log_file.message(&scenario.to_string());
+    log_file.message(&format!("mutation diff:\n{}", mutant.diff()));
+    log_file.message(&format!(
+        "built together with the other mutants of {}; test with {ACTIVE_MUTANT_ENV}={id}",
+        mutant.package_name()
+    ));
+    let test_names = test_coverage.and_then(|c| c.covering_tests(mutant));
+    if let Some(test_names) = &test_names {
+        log_covering_tests(&mut log_file, test_names);
+    }
+    console.scenario_started(&scenario, log_file.path())?;
+    let mut outcome = ScenarioOutcome::new(&log_file, scenario.clone());
+    outcome.add_phase_result(build_result.clone());
+    console.scenario_phase_started(&scenario, Phase::Test);
+    let phase_result = run_cargo(
+        build_dir,
+        Some(&[mutant.package()]),
+        Phase::Test,
+        timeouts.test,
+        &mut log_file,
+        &[(ACTIVE_MUTANT_ENV.to_owned(), id.to_string())],
+        test_names.as_deref(),
+        options,
+        console,
+    )?;
+    outcome.add_phase_result(phase_result);
+    console.scenario_phase_finished(&scenario, Phase::Test);
+    output_mutex
+        .lock()
+        .expect("lock output dir to add outcome")
+        .add_scenario_outcome(&outcome)?;
+    debug!(outcome = ?outcome.summary());
+    console.scenario_finished(&scenario, &outcome, options);
+    Ok(())
 }
 
 #[derive(Copy, Clone)]
@@ -270,16 +677,29 @@ fn build_timeout(baseline_duration: Option<Duration>, options: &Options) -> Dura
     )
 }
 
+/// Record in a mutant's log which tests will be run against it.
+fn log_covering_tests(log_file: &mut LogFile, test_names: &[String]) {
+    log_file.message(&format!(
+        "running only the tests that execute this code: {}",
+        test_names.join(", ")
+    ));
+}
+
 /// Test various phases of one scenario in a build dir.
 ///
 /// The [BuildDir] is passed as mutable because it's for the exclusive use of this function for the
 /// duration of the test.
+///
+/// If `test_coverage` is given, only the tests that cover a mutant are run against it,
+/// unless it's not known which tests do.
+#[allow(clippy::too_many_arguments)]
 fn test_scenario(
     build_dir: &BuildDir,
     output_mutex: &Mutex<OutputDir>,
     scenario: &Scenario,
     test_packages: &[&Package],
     timeouts: Timeouts,
+    test_coverage: Option<&TestCoverage>,
     options: &Options,
     console: &Console,
 ) -> Result<ScenarioOutcome> {
@@ -297,6 +717,12 @@ fn test_scenario(
             mutant.apply(build_dir)
         })
         .transpose()?;
+    let test_names = scenario
+        .mutant()
+        .and_then(|mutant| test_coverage?.covering_tests(mutant));
+    if let Some(test_names) = &test_names {
+        log_covering_tests(&mut log_file, test_names);
+    }
     console.scenario_started(scenario, log_file.path())?;
 
     let mut outcome = ScenarioOutcome::new(&log_file, scenario.clone());
@@ -317,6 +743,8 @@ fn test_scenario(
             phase,
             timeout,
             &mut log_file,
+            &[],
+            test_names.as_deref(),
             options,
             console,
         )?;
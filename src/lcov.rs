@@ -0,0 +1,254 @@
+// Copyright 2024 Martin Pool
+
+//! Read line coverage from LCOV tracefiles, as written by `llvm-cov export -format=lcov`
+//! and `cargo llvm-cov --lcov`.
+//!
+//! Only the source file (`SF`) and line hit count (`DA`) records are used; function
+//! and branch records are ignored.
+
+use std::collections::{BTreeMap, HashMap};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use itertools::Itertools;
+use tracing::{debug, warn};
+
+/// The number of times each line was executed, by source file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineCoverage {
+    files: HashMap<Utf8PathBuf, BTreeMap<usize, u64>>,
+}
+
+impl LineCoverage {
+    /// Parse LCOV text.
+    ///
+    /// Lines that aren't LCOV records are ignored, so that this can read the output of
+    /// a command mixed with other messages.
+    pub fn parse(lcov: &str) -> LineCoverage {
+        let mut files: HashMap<Utf8PathBuf, BTreeMap<usize, u64>> = HashMap::new();
+        let mut current: Option<&mut BTreeMap<usize, u64>> = None;
+        for line in lcov.lines() {
+            let line = line.trim_end();
+            if let Some(path) = line.strip_prefix("SF:") {
+                current = Some(files.entry(Utf8PathBuf::from(path)).or_default());
+            } else if line == "end_of_record" {
+                current = None;
+            } else if let Some(data) = line.strip_prefix("DA:") {
+                let Some(hits_by_line) = current.as_mut() else {
+                    warn!("LCOV line data outside of a source file record: {line:?}");
+                    continue;
+                };
+                let mut fields = data.split(',');
+                match (
+                    fields.next().and_then(|l| l.parse::<usize>().ok()),
+                    fields.next().and_then(|h| h.parse::<u64>().ok()),
+                ) {
+                    (Some(line_number), Some(hits)) => {
+                        // The same line can be reported more than once, for example for
+                        // different instantiations of a generic function.
+                        *hits_by_line.entry(line_number).or_default() += hits;
+                    }
+                    _ => warn!("Can't parse LCOV line data {line:?}"),
+                }
+            }
+        }
+        LineCoverage { files }
+    }
+
+    /// Return the source files mentioned in the coverage data.
+    pub fn paths(&self) -> impl Iterator<Item = &Utf8Path> {
+        self.files.keys().map(|p| p.as_path())
+    }
+
+    /// Return the hit counts for each instrumented line in a file, if it's known.
+    pub fn lines(&self, path: &Utf8Path) -> Option<&BTreeMap<usize, u64>> {
+        self.files.get(path)
+    }
+
+    /// Return the hit counts of the files in the report by their path relative to the
+    /// tree in `tree_dir`.
+    ///
+    /// Relative paths in the report are taken as they are. Absolute paths might be
+    /// within `tree_dir`, or within a copy of the tree in a different directory, such as
+    /// on a CI machine. In that case the root of the copy is taken to be the outermost
+    /// directory containing all the paths under which at least one of them is also a
+    /// file in `tree_dir`.
+    ///
+    /// Files outside of the root, or outside the tree, are left out.
+    pub fn by_tree_path(&self, tree_dir: &Utf8Path) -> HashMap<Utf8PathBuf, &BTreeMap<usize, u64>> {
+        let root = self.find_root(tree_dir);
+        debug!(?root, "Found the root of the paths in the coverage report");
+        self.files
+            .iter()
+            .filter_map(|(path, lines)| {
+                let relative = if path.is_absolute() {
+                    path.strip_prefix(root.as_ref()?).ok()?
+                } else {
+                    path.as_path()
+                };
+                Some((relative.to_owned(), lines))
+            })
+            .collect()
+    }
+
+    /// Find the directory that the absolute paths in the report are relative to.
+    fn find_root(&self, tree_dir: &Utf8Path) -> Option<Utf8PathBuf> {
+        let absolute_paths = self.paths().filter(|path| path.is_absolute()).collect_vec();
+        let tree_dir = tree_dir.canonicalize_utf8().unwrap_or(tree_dir.to_owned());
+        if absolute_paths
+            .iter()
+            .any(|path| path.starts_with(&tree_dir))
+        {
+            return Some(tree_dir);
+        }
+        let mut common = absolute_paths.first()?.parent()?;
+        for path in &absolute_paths {
+            while !path.starts_with(common) {
+                common = common.parent()?;
+            }
+        }
+        // Look from the outside in, so that a path like `member/src/lib.rs` isn't
+        // mistaken for `src/lib.rs` if both are in the tree.
+        let mut ancestors = common.ancestors().collect_vec();
+        ancestors.reverse();
+        ancestors
+            .into_iter()
+            .find(|ancestor| {
+                absolute_paths.iter().any(|path| {
+                    path.strip_prefix(ancestor)
+                        .is_ok_and(|relative| tree_dir.join(relative).is_file())
+                })
+            })
+            .map(Utf8Path::to_owned)
+    }
+
+    /// Return the numbers of the lines in a file that were executed at least once.
+    pub fn hit_lines(&self, path: &Utf8Path) -> Vec<usize> {
+        self.lines(path)
+            .map(|lines| {
+                lines
+                    .iter()
+                    .filter(|(_, &hits)| hits > 0)
+                    .map(|(&line, _)| line)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn parse_lcov() {
+        let coverage = LineCoverage::parse(indoc! { "
+            SF:/src/tree/src/lib.rs
+            FN:4,_RNvCs_factorial
+            FNDA:2,_RNvCs_factorial
+            DA:4,2
+            DA:5,0
+            DA:6,10
+            DA:6,3
+            BRF:0
+            end_of_record
+            result: Success
+            SF:/src/tree/src/other.rs
+            DA:1,0
+            end_of_record
+        " });
+        assert_eq!(
+            coverage.paths().collect::<std::collections::BTreeSet<_>>(),
+            [
+                Utf8Path::new("/src/tree/src/lib.rs"),
+                Utf8Path::new("/src/tree/src/other.rs")
+            ]
+            .into()
+        );
+        assert_eq!(
+            coverage.lines("/src/tree/src/lib.rs".into()),
+            Some(&BTreeMap::from([(4, 2), (5, 0), (6, 13)]))
+        );
+        assert_eq!(coverage.hit_lines("/src/tree/src/lib.rs".into()), [4, 6]);
+        assert_eq!(
+            coverage.hit_lines("/src/tree/src/other.rs".into()),
+            [] as [usize; 0]
+        );
+        assert_eq!(
+            coverage.hit_lines("/src/tree/src/missing.rs".into()),
+            [] as [usize; 0]
+        );
+    }
+
+    #[test]
+    fn relative_paths_are_used_as_they_are() {
+        let coverage = LineCoverage::parse(indoc! { "
+            SF:src/main.rs
+            DA:3,4
+            end_of_record
+        " });
+        assert_eq!(
+            coverage.by_tree_path("testdata/workspace".into()),
+            HashMap::from([(Utf8PathBuf::from("src/main.rs"), &BTreeMap::from([(3, 4)]))])
+        );
+    }
+
+    #[test]
+    fn absolute_paths_in_the_tree() {
+        let tree_dir = Utf8Path::new("testdata/workspace")
+            .canonicalize_utf8()
+            .unwrap();
+        let coverage = LineCoverage::parse(&format!(
+            "SF:{tree_dir}/utils/src/lib.rs\nDA:1,1\nend_of_record\n\
+            SF:/home/user/.cargo/registry/src/dep/lib.rs\nDA:2,0\nend_of_record\n"
+        ));
+        assert_eq!(
+            coverage.by_tree_path("testdata/workspace".into()),
+            HashMap::from([(
+                Utf8PathBuf::from("utils/src/lib.rs"),
+                &BTreeMap::from([(1, 1)])
+            )])
+        );
+    }
+
+    #[test]
+    fn absolute_paths_from_a_copy_of_the_tree() {
+        let coverage = LineCoverage::parse(indoc! { "
+            SF:/ci/checkout/utils/src/lib.rs
+            DA:1,1
+            end_of_record
+            SF:/ci/checkout/main/src/main.rs
+            DA:2,0
+            end_of_record
+        " });
+        let by_path = coverage.by_tree_path("testdata/workspace".into());
+        assert_eq!(
+            by_path.keys().sorted().collect_vec(),
+            ["main/src/main.rs", "utils/src/lib.rs"]
+        );
+
+        // With only one file from a member in the report, it's not matched to a file
+        // of the same name at the top of the tree.
+        let coverage = LineCoverage::parse(indoc! { "
+            SF:/ci/checkout/member/src/lib.rs
+            DA:1,1
+            end_of_record
+        " });
+        let tmp = tempfile::tempdir().unwrap();
+        let tree_dir = Utf8Path::from_path(tmp.path()).unwrap();
+        for path in ["src/lib.rs", "member/src/lib.rs"] {
+            std::fs::create_dir_all(tree_dir.join(path).parent().unwrap()).unwrap();
+            std::fs::write(tree_dir.join(path), "").unwrap();
+        }
+        assert_eq!(
+            coverage.by_tree_path(tree_dir).keys().collect_vec(),
+            ["member/src/lib.rs"]
+        );
+
+        // Files that aren't in the tree aren't matched at all.
+        let coverage = LineCoverage::parse("SF:/ci/checkout/src/other.rs\nDA:1,0\nend_of_record\n");
+        assert_eq!(coverage.by_tree_path(tree_dir), HashMap::new());
+    }
+}
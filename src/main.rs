@@ -9,12 +9,15 @@ mod cargo;
 mod config;
 mod console;
 mod copy_tree;
+mod coverage;
 mod exit_code;
 mod fnvalue;
 mod glob;
 mod in_diff;
+mod incremental;
 mod interrupt;
 mod lab;
+mod lcov;
 mod list;
 mod log_file;
 mod manifest;
@@ -27,6 +30,7 @@ mod path;
 mod pretty;
 mod process;
 mod scenario;
+mod schemata;
 mod shard;
 mod source;
 mod span;
@@ -46,10 +50,10 @@ use clap::builder::Styles;
 use clap::{ArgAction, CommandFactory, Parser, ValueEnum};
 use clap_complete::{generate, Shell};
 use color_print::cstr;
-use tracing::debug;
+use tracing::{debug, info};
 
 use crate::build_dir::BuildDir;
-use crate::console::Console;
+use crate::console::{plural, Console};
 use crate::in_diff::diff_filter;
 use crate::interrupt::check_interrupted;
 use crate::lab::test_mutants;
@@ -150,6 +154,11 @@ pub struct Args {
     #[arg(long)]
     completions: Option<Shell>,
 
+    /// Skip mutants in lines never executed according to this LCOV coverage report,
+    /// and list them in uncovered.txt.
+    #[arg(long, help_heading = "Filters")]
+    coverage_report: Option<Utf8PathBuf>,
+
     /// Return this error values from functions returning Result:
     /// for example, `::anyhow::anyhow!("mutated")`.
     #[arg(long, help_heading = "Generate")]
@@ -180,6 +189,10 @@ pub struct Args {
     #[arg(long, short = 'f', help_heading = "Filters")]
     file: Vec<String>,
 
+    /// Generate mutants of this genre, in addition to the default genres.
+    #[arg(long, help_heading = "Generate")]
+    genre: Vec<Genre>,
+
     /// Don't copy files matching gitignore patterns.
     #[arg(long, action = ArgAction::Set, default_value = "true", help_heading = "Copying", group = "copy_opts")]
     gitignore: bool,
@@ -193,6 +206,10 @@ pub struct Args {
     )]
     in_place: bool,
 
+    /// Reuse outcomes from the previous run for mutants that haven't changed.
+    #[arg(long, help_heading = "Execution")]
+    incremental: bool,
+
     /// Run this many cargo build/test jobs in parallel.
     #[arg(
         long,
@@ -268,6 +285,11 @@ pub struct Args {
     #[arg(id = "package", long, short = 'p', help_heading = "Filters")]
     mutate_packages: Vec<String>,
 
+    /// Continue an interrupted run in the existing mutants.out, testing only the mutants
+    /// that don't have an outcome yet.
+    #[arg(long, help_heading = "Execution")]
+    resume: bool,
+
     /// Run mutants in random order.
     #[arg(long, help_heading = "Execution")]
     shuffle: bool,
@@ -276,6 +298,16 @@ pub struct Args {
     #[arg(long, help_heading = "Execution")]
     no_shuffle: bool,
 
+    /// Build all the mutants in each package together, switched on at runtime, rather
+    /// than building each mutant separately.
+    #[arg(long, help_heading = "Execution")]
+    schemata: bool,
+
+    /// Measure which tests cover each function, and run only those tests against its
+    /// mutants. Needs the llvm-tools-preview rustup component.
+    #[arg(long, help_heading = "Execution")]
+    select_tests: bool,
+
     /// Run only one shard of all generated mutants: specify as e.g. 1/4.
     #[arg(long, help_heading = "Execution")]
     shard: Option<Shard>,
@@ -422,6 +454,13 @@ fn main() -> Result<()> {
     if args.list {
         list_mutants(FmtToIoWrite::new(io::stdout()), &mutants, &options)?;
     } else {
+        if !discovered.skipped_generic_fns.is_empty() {
+            info!(
+                "No function body replacements for {} returning a generic type without a `Default`, `From`, or numeric bound: {}",
+                plural(discovered.skipped_generic_fns.len(), "function"),
+                discovered.skipped_generic_fns.join(", ")
+            );
+        }
         let lab_outcome = test_mutants(mutants, &workspace.dir, options, &console)?;
         exit(lab_outcome.exit_code());
     }
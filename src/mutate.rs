@@ -9,8 +9,9 @@ use std::sync::Arc;
 use anyhow::{ensure, Context, Result};
 use console::{style, StyledObject};
 use serde::ser::{SerializeStruct, Serializer};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use similar::TextDiff;
+use strum::{Display, EnumString};
 use tracing::error;
 use tracing::trace;
 
@@ -21,13 +22,47 @@ use crate::span::Span;
 use crate::MUTATION_MARKER_COMMENT;
 
 /// Various broad categories of mutants.
-#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+///
+/// Genres are named in `--genre` and the config file by the same names
+/// used in the json output, like `FnValue`, although case is not significant.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize, Deserialize, EnumString, Display)]
+#[strum(ascii_case_insensitive)]
 pub enum Genre {
     /// Replace the body of a function with a fixed value.
     FnValue,
+    /// Replace a literal value, like `1024` or `"hello"`, with a different value.
+    Literal,
     /// Replace `==` with `!=` and so on.
     BinaryOperator,
     UnaryOperator,
+    /// Replace the condition of an `if`, `while`, or match guard with `true` or `false`.
+    Condition,
+    /// Delete a match arm that would be handled by a `_` arm, or empty an `else` block.
+    Branch,
+    /// Delete a statement that calls a function or method, like `vec.push(x);`.
+    Statement,
+    /// Swap `..` and `..=` in ranges, or move the end of a range by one.
+    Range,
+    /// Drop errors returned by `?`, or change the fallback of `unwrap_or` and similar.
+    ErrorHandling,
+    /// Swap a method call for its counterpart, like `min` for `max`.
+    MethodSwap,
+    /// Remove a call that moves or resets state, like `mem::take` or `clear()`.
+    StateChange,
+    /// Replace a field in a struct literal with `Default::default()`.
+    StructField,
+    /// Replace the value of a `const` or `static` item.
+    Constant,
+}
+
+impl Genre {
+    /// True if mutants of this genre are generated without being turned on by `--genre`.
+    pub fn is_default(&self) -> bool {
+        matches!(
+            self,
+            Genre::FnValue | Genre::BinaryOperator | Genre::UnaryOperator
+        )
+    }
 }
 
 /// A mutation applied to source code.
@@ -49,6 +84,10 @@ pub struct Mutant {
     /// The replacement text.
     pub replacement: String,
 
+    /// A short description of the replaced code, to use in the mutant's name instead
+    /// of the full text of the span, which might be long.
+    pub short_replaced: Option<String>,
+
     /// What general category of mutant this is.
     pub genre: Genre,
 }
@@ -139,7 +178,14 @@ impl Mutant {
             } else {
                 v.push(s("replace "));
             }
-            v.push(s(self.original_text()).yellow());
+            if self.genre == Genre::Condition {
+                v.push(s("condition "));
+            }
+            if let Some(short_replaced) = &self.short_replaced {
+                v.push(s(short_replaced).yellow());
+            } else {
+                v.push(s(self.original_text()).yellow());
+            }
             if !self.replacement.is_empty() {
                 v.push(s(" with "));
                 v.push(s(&self.replacement).bright().yellow());
@@ -347,6 +393,9 @@ mod test {
         replace controlled_loop with ()
         replace > with == in controlled_loop
         replace > with < in controlled_loop
+        replace > with >= in controlled_loop
+        replace start.elapsed() > Duration::from_secs(60 * 5) with true in controlled_loop
+        replace start.elapsed() > Duration::from_secs(60 * 5) with false in controlled_loop
         replace * with + in controlled_loop
         replace * with / in controlled_loop
         "###
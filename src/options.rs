@@ -5,6 +5,7 @@
 //! The [Options] structure is built from command-line options and then widely passed around.
 //! Options are also merged from the [config] after reading the command line arguments.
 
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use globset::GlobSet;
@@ -33,6 +34,9 @@ pub struct Options {
     /// Don't copy at all; run tests in the source directory.
     pub in_place: bool,
 
+    /// Reuse outcomes from the previous run for unchanged mutants.
+    pub incremental: bool,
+
     /// Don't delete scratch directories.
     pub leak_dirs: bool,
 
@@ -70,6 +74,17 @@ pub struct Options {
     /// List mutants with line and column numbers.
     pub show_line_col: bool,
 
+    /// Build the mutants in each package together, switched on at runtime, and then
+    /// run the tests for each mutant.
+    pub schemata: bool,
+
+    /// Continue an interrupted run in the existing output directory.
+    pub resume: bool,
+
+    /// Measure the coverage of each test, and run only the tests that cover the function
+    /// containing each mutant.
+    pub select_tests: bool,
+
     /// Test mutants in random order.
     ///
     /// This is now the default, so that repeated partial runs are more likely to find
@@ -97,6 +112,9 @@ pub struct Options {
     /// Mutants to skip, as a regexp matched against the full name.
     pub exclude_names: RegexSet,
 
+    /// Skip mutants in code that this LCOV report says is never executed.
+    pub coverage_report: Option<Utf8PathBuf>,
+
     /// Create `mutants.out` within this directory (by default, the source directory).
     pub output_in_dir: Option<Utf8PathBuf>,
 
@@ -106,6 +124,16 @@ pub struct Options {
     /// Insert these values as errors from functions returning `Result`.
     pub error_values: Vec<String>,
 
+    /// Generate mutants of these genres, in addition to the ones generated by default.
+    pub genres: Vec<Genre>,
+
+    /// Pairs of method names to swap for each other, in addition to the built-in pairs.
+    pub method_swaps: Vec<(String, String)>,
+
+    /// Replacement expressions for functions returning types that match these patterns,
+    /// with `$T` standing for replacements of the generic argument `T`.
+    pub replacements: BTreeMap<String, Vec<String>>,
+
     /// Show ANSI colors.
     pub colors: Colors,
 
@@ -133,7 +161,7 @@ pub enum TestTool {
 }
 
 /// Join two slices into a new vector.
-fn join_slices(a: &[String], b: &[String]) -> Vec<String> {
+fn join_slices<T: Clone>(a: &[T], b: &[T]) -> Vec<T> {
     a.iter().chain(b).cloned().collect()
 }
 
@@ -198,6 +226,7 @@ impl Options {
             baseline: args.baseline,
             check_only: args.check,
             colors: args.colors,
+            coverage_report: args.coverage_report.clone(),
             emit_json: args.json,
             emit_diffs: args.diff,
             error_values: join_slices(&args.error, &config.error_values),
@@ -208,14 +237,21 @@ impl Options {
             examine_globset: build_glob_set(or_slices(&args.file, &config.examine_globs))?,
             exclude_globset: build_glob_set(or_slices(&args.exclude, &config.exclude_globs))?,
             features: args.features.clone(),
+            genres: join_slices(&args.genre, &config.genres),
             gitignore: args.gitignore,
             in_place: args.in_place,
+            incremental: args.incremental,
             jobs: args.jobs,
             leak_dirs: args.leak_dirs,
+            method_swaps: config.method_swaps.clone(),
             minimum_test_timeout,
             output_in_dir: args.output.clone(),
             print_caught: args.caught,
             print_unviable: args.unviable,
+            replacements: config.replacements.clone(),
+            resume: args.resume,
+            schemata: args.schemata,
+            select_tests: args.select_tests,
             shuffle: !args.no_shuffle,
             show_line_col: args.line_col,
             show_times: !args.no_times,
@@ -257,6 +293,7 @@ fn or_slices<'a: 'c, 'b: 'c, 'c, T>(a: &'a [T], b: &'b [T]) -> &'c [T] {
 #[cfg(test)]
 mod test {
     use std::io::Write;
+    use std::str::FromStr;
 
     use indoc::indoc;
     use rusty_fork::rusty_fork_test;
@@ -370,6 +407,55 @@ mod test {
         assert_eq!(options.test_tool, TestTool::Nextest);
     }
 
+    #[test]
+    fn genres_from_args_and_config() {
+        let args = Args::parse_from(["mutants"]);
+        let options = Options::new(&args, &Config::default()).unwrap();
+        assert_eq!(options.genres, []);
+
+        let args = Args::parse_from(["mutants", "--genre=condition"]);
+        let options = Options::new(&args, &Config::default()).unwrap();
+        assert_eq!(options.genres, [Genre::Condition]);
+
+        let args = Args::parse_from(["mutants"]);
+        let config = Config::from_str(r#"genres = ["Condition"]"#).unwrap();
+        let options = Options::new(&args, &config).unwrap();
+        assert_eq!(options.genres, [Genre::Condition]);
+
+        Args::try_parse_from(["mutants", "--genre=nonsense"])
+            .expect_err("unknown genre should be rejected");
+    }
+
+    #[test]
+    fn method_swaps_from_config() {
+        let args = Args::parse_from(["mutants"]);
+        let config = Config::from_str(r#"method_swaps = [["read", "write"]]"#).unwrap();
+        let options = Options::new(&args, &config).unwrap();
+        assert_eq!(
+            options.method_swaps,
+            [("read".to_owned(), "write".to_owned())]
+        );
+    }
+
+    #[test]
+    fn replacements_from_config() {
+        let args = Args::parse_from(["mutants"]);
+        let config = Config::from_str(indoc! { r#"
+            [replacements]
+            UserId = ["UserId(1)"]
+            "Id<T>" = ["Id::new($T)"]
+        "# })
+        .unwrap();
+        let options = Options::new(&args, &config).unwrap();
+        assert_eq!(
+            options.replacements,
+            BTreeMap::from([
+                ("Id<T>".to_owned(), vec!["Id::new($T)".to_owned()]),
+                ("UserId".to_owned(), vec!["UserId(1)".to_owned()]),
+            ])
+        );
+    }
+
     #[test]
     fn features_arg() {
         let args = Args::try_parse_from(["mutants", "--features", "nice,shiny features"]).unwrap();
@@ -9,6 +9,7 @@ use std::time::Instant;
 
 use humantime::format_duration;
 use serde::ser::SerializeStruct;
+use serde::Deserialize;
 use serde::Serialize;
 use serde::Serializer;
 use tracing::warn;
@@ -28,7 +29,7 @@ use crate::*;
 /// 3. `cargo tests` -- do the tests pass?
 ///
 /// Some scenarios such as freshening the tree don't run the tests.
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum Phase {
     Check,
     Build,
@@ -88,6 +89,11 @@ impl LabOutcome {
         self.outcomes.push(outcome);
     }
 
+    /// Return the number of outcomes reused from a previous run.
+    pub fn reused(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.reused).count()
+    }
+
     /// Return the overall program exit code reflecting this outcome.
     pub fn exit_code(&self) -> i32 {
         // TODO: Maybe move this into an error returned from experiment()?
@@ -134,6 +140,10 @@ impl LabOutcome {
             by_outcome.push(format!("{} succeeded", self.success));
         }
         s.push(by_outcome.join(", "));
+        let reused = self.reused();
+        if reused != 0 {
+            s.push(format!(" ({reused} reused from the previous run)"));
+        }
         s.join("")
     }
 }
@@ -148,6 +158,11 @@ pub struct ScenarioOutcome {
     pub scenario: Scenario,
     /// For each phase, the duration and the cargo result.
     phase_results: Vec<PhaseResult>,
+    /// A hash of the mutated code and the tests, used to recognize unchanged mutants
+    /// in a later `--incremental` run.
+    pub fingerprint: Option<String>,
+    /// True if this outcome was copied from a previous run, rather than tested again.
+    pub reused: bool,
 }
 
 impl Serialize for ScenarioOutcome {
@@ -156,11 +171,13 @@ impl Serialize for ScenarioOutcome {
         S: Serializer,
     {
         // custom serialize to omit inessential info
-        let mut ss = serializer.serialize_struct("Outcome", 4)?;
+        let mut ss = serializer.serialize_struct("Outcome", 6)?;
         ss.serialize_field("scenario", &self.scenario)?;
         ss.serialize_field("log_path", &self.log_path)?;
         ss.serialize_field("summary", &self.summary())?;
         ss.serialize_field("phase_results", &self.phase_results)?;
+        ss.serialize_field("fingerprint", &self.fingerprint)?;
+        ss.serialize_field("reused", &self.reused)?;
         ss.end()
     }
 }
@@ -171,6 +188,24 @@ impl ScenarioOutcome {
             log_path: log_file.path().to_owned(),
             scenario,
             phase_results: Vec::new(),
+            fingerprint: None,
+            reused: false,
+        }
+    }
+
+    /// Make an outcome for a scenario tested in a previous run, whose log is in `log_path`.
+    pub fn previous(
+        log_path: Utf8PathBuf,
+        scenario: Scenario,
+        phase_results: Vec<PhaseResult>,
+        fingerprint: Option<String>,
+    ) -> ScenarioOutcome {
+        ScenarioOutcome {
+            log_path,
+            scenario,
+            phase_results,
+            fingerprint,
+            reused: false,
         }
     }
 
@@ -303,7 +338,7 @@ impl Serialize for PhaseResult {
 }
 
 /// Overall summary outcome for one mutant.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub enum SummaryOutcome {
     Success,
     CaughtMutant,
@@ -340,6 +375,8 @@ mod test {
                     argv: vec!["cargo".into(), "test".into()],
                 },
             ],
+            fingerprint: None,
+            reused: false,
         };
         assert_eq!(
             outcome.phase_result(Phase::Build),
@@ -2,19 +2,22 @@
 
 //! A `mutants.out` directory holding logs and other output.
 
+use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::Path;
 use std::thread::sleep;
 use std::time::Duration;
 
+use anyhow::bail;
 use fs2::FileExt;
 use path_slash::PathExt;
 use serde::Serialize;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
-use tracing::info;
+use tracing::{info, warn};
 
+use crate::incremental::{read_outcomes, Fingerprints};
 use crate::outcome::{LabOutcome, SummaryOutcome};
 use crate::*;
 
@@ -95,6 +98,8 @@ pub struct OutputDir {
     unviable_list: File,
     /// The accumulated overall lab outcome.
     pub lab_outcome: LabOutcome,
+    /// Fingerprints recorded with each outcome, to recognize unchanged mutants later.
+    fingerprints: Fingerprints,
 }
 
 impl OutputDir {
@@ -131,8 +136,33 @@ impl OutputDir {
             .context("create lock.json lock file")?;
         let log_dir = output_dir.join("log");
         fs::create_dir(&log_dir).with_context(|| format!("create log directory {:?}", &log_dir))?;
+        OutputDir::open_locked(output_dir, lock_file)
+    }
+
+    /// Open an existing `mutants.out` directory within the given directory, to add more
+    /// outcomes to it.
+    ///
+    /// Returns None if there is no `mutants.out` directory. Otherwise, this waits for the lock
+    /// on `lock.json`, as with [OutputDir::new], but the directory is not rotated.
+    pub fn open_existing(in_dir: &Utf8Path) -> Result<Option<OutputDir>> {
+        let output_dir = in_dir.join(OUTDIR_NAME);
+        if !output_dir.is_dir() {
+            return Ok(None);
+        }
+        let lock_file = LockFile::acquire_lock(output_dir.as_std_path())
+            .context("lock existing output directory")?;
+        let log_dir = output_dir.join("log");
+        if !log_dir.is_dir() {
+            fs::create_dir(&log_dir)
+                .with_context(|| format!("create log directory {:?}", &log_dir))?;
+        }
+        OutputDir::open_locked(output_dir, lock_file).map(Some)
+    }
 
-        // Create text list files.
+    /// Open or create the list files in a locked output directory.
+    fn open_locked(output_dir: Utf8PathBuf, lock_file: File) -> Result<OutputDir> {
+        let log_dir = output_dir.join("log");
+        // Create text list files, or append to them if they exist.
         let mut list_file_options = OpenOptions::new();
         list_file_options.create(true).append(true);
         let missed_list = list_file_options
@@ -156,6 +186,7 @@ impl OutputDir {
             caught_list,
             timeout_list,
             unviable_list,
+            fingerprints: Fingerprints::default(),
         })
     }
 
@@ -167,6 +198,32 @@ impl OutputDir {
         LogFile::create_in(&self.log_dir, &scenario.log_file_name_base())
     }
 
+    /// Create a new log for building the combined mutants of one package.
+    pub fn create_schemata_log(&self, package_name: &str) -> Result<LogFile> {
+        LogFile::create_in(&self.log_dir, &format!("schemata_{package_name}"))
+    }
+
+    /// Create a new log for measuring the coverage of each test.
+    pub fn create_coverage_log(&self) -> Result<LogFile> {
+        LogFile::create_in(&self.log_dir, "coverage")
+    }
+
+    /// Set the fingerprints to record with mutant outcomes.
+    pub fn set_fingerprints(&mut self, fingerprints: Fingerprints) {
+        self.fingerprints = fingerprints;
+    }
+
+    /// Return the fingerprint of a mutant, as it would be recorded with its outcome.
+    pub fn fingerprint(&self, mutant: &Mutant) -> Option<String> {
+        self.fingerprints.fingerprint(mutant)
+    }
+
+    /// Return the path of the previous output directory, `mutants.out.old`, if it exists.
+    pub fn previous_path(&self) -> Option<Utf8PathBuf> {
+        let path = self.path.parent()?.join(ROTATED_NAME);
+        path.is_dir().then_some(path)
+    }
+
     #[allow(dead_code)]
     /// Return the path of the `mutants.out` directory.
     pub fn path(&self) -> &Utf8Path {
@@ -186,7 +243,11 @@ impl OutputDir {
 
     /// Add the result of testing one scenario.
     pub fn add_scenario_outcome(&mut self, scenario_outcome: &ScenarioOutcome) -> Result<()> {
-        self.lab_outcome.add(scenario_outcome.to_owned());
+        let mut recorded = scenario_outcome.to_owned();
+        if let Some(mutant) = scenario_outcome.scenario.mutant() {
+            recorded.fingerprint = self.fingerprint(mutant);
+        }
+        self.lab_outcome.add(recorded);
         self.write_lab_outcome()?;
         let scenario = &scenario_outcome.scenario;
         if let Scenario::Mutant(mutant) = scenario {
@@ -219,6 +280,74 @@ impl OutputDir {
         .context("write mutants.json")
     }
 
+    /// Continue an interrupted run in this directory.
+    ///
+    /// The mutants must be the same as those in `mutants.json`, although possibly in a
+    /// different order, or this returns an error. The mutant outcomes already in `outcomes.json`
+    /// are restored, and the mutants that don't have an outcome yet are returned in the order
+    /// in which they were originally going to be tested.
+    pub fn resume(&mut self, mutants: Vec<Mutant>) -> Result<Vec<Mutant>> {
+        let mutants_json_path = self.path.join("mutants.json");
+        let previous_mutants: Vec<serde_json::Value> = serde_json::from_str(
+            &fs::read_to_string(&mutants_json_path)
+                .with_context(|| format!("read {mutants_json_path:?}"))?,
+        )
+        .with_context(|| format!("parse {mutants_json_path:?}"))?;
+        let mut by_json: HashMap<String, Vec<Mutant>> = HashMap::new();
+        for mutant in mutants {
+            by_json
+                .entry(serde_json::to_value(&mutant)?.to_string())
+                .or_default()
+                .push(mutant);
+        }
+        let pending: Option<Vec<(String, Mutant)>> = previous_mutants
+            .iter()
+            .map(|previous| {
+                let json = previous.to_string();
+                let mutant = by_json.get_mut(&json).and_then(Vec::pop)?;
+                Some((json, mutant))
+            })
+            .collect();
+        let mut pending = match pending {
+            Some(pending) if by_json.values().all(Vec::is_empty) => pending,
+            _ => bail!(
+                "The mutants in {mutants_json_path:?} don't match the source tree; run again without --resume"
+            ),
+        };
+
+        let outcomes_json_path = self.path.join("outcomes.json");
+        let previous_outcomes = if outcomes_json_path.exists() {
+            read_outcomes(&outcomes_json_path)?
+        } else {
+            Vec::new()
+        };
+        for previous_outcome in previous_outcomes {
+            let Some(mutant_json) = previous_outcome.mutant_json().map(|j| j.to_string()) else {
+                // The baseline is run again.
+                continue;
+            };
+            let Some(index) = pending.iter().position(|(json, _)| *json == mutant_json) else {
+                warn!("Previous outcome doesn't match any mutant: {mutant_json}");
+                continue;
+            };
+            let (_, mutant) = pending.remove(index);
+            self.lab_outcome
+                .add(previous_outcome.into_outcome(Scenario::Mutant(mutant)));
+        }
+        Ok(pending.into_iter().map(|(_, mutant)| mutant).collect())
+    }
+
+    /// Write the list of mutants that were skipped because they're not covered by tests.
+    pub fn write_uncovered_list(&self, mutants: &[Mutant]) -> Result<()> {
+        let mut file = BufWriter::new(
+            File::create(self.path.join("uncovered.txt")).context("create uncovered.txt")?,
+        );
+        for mutant in mutants {
+            writeln!(file, "{}", mutant.name(true, false)).context("write uncovered.txt")?;
+        }
+        file.flush().context("write uncovered.txt")
+    }
+
     pub fn take_lab_outcome(self) -> LabOutcome {
         self.lab_outcome
     }
@@ -338,4 +467,27 @@ mod test {
             .join("mutants.out.old/log/baseline.log")
             .is_file());
     }
+
+    #[test]
+    fn open_existing_does_not_rotate() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let temp_dir_path = Utf8Path::from_path(temp_dir.path()).unwrap();
+        assert!(OutputDir::open_existing(temp_dir_path).unwrap().is_none());
+
+        let output_dir = OutputDir::new(temp_dir_path).unwrap();
+        output_dir.create_log(&Scenario::Baseline).unwrap();
+        drop(output_dir);
+
+        let output_dir = OutputDir::open_existing(temp_dir_path).unwrap().unwrap();
+        output_dir.create_log(&Scenario::Baseline).unwrap();
+        assert!(!temp_dir.path().join("mutants.out.old").exists());
+        assert!(temp_dir
+            .path()
+            .join("mutants.out/log/baseline.log")
+            .is_file());
+        assert!(temp_dir
+            .path()
+            .join("mutants.out/log/baseline_001.log")
+            .is_file());
+    }
 }
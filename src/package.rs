@@ -12,4 +12,8 @@ pub struct Package {
 
     /// For Cargo, the path of the `Cargo.toml` manifest file, relative to the top of the tree.
     pub relative_manifest_path: Utf8PathBuf,
+
+    /// The directories of packages this one depends on by path, directly or through
+    /// other members of the workspace.
+    pub path_dependency_dirs: Vec<Utf8PathBuf>,
 }
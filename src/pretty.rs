@@ -2,7 +2,7 @@
 
 //! Convert a token stream back to (reasonably) pretty Rust code in a string.
 
-use proc_macro2::{Delimiter, TokenTree};
+use proc_macro2::{Delimiter, Spacing, TokenTree};
 use quote::ToTokens;
 
 /// Convert something to a pretty-printed string.
@@ -26,12 +26,53 @@ where
         use TokenTree::*;
         let mut b = String::with_capacity(200);
         let mut ts = self.to_token_stream().into_iter().peekable();
+        // True while inside the `|...|` parameter list of a closure.
+        let mut closure_params = false;
+        // True if the previous token ends an operand, so that a following `-` or `*` is
+        // a binary operator rather than a negation or dereference.
+        let mut after_operand = false;
         while let Some(tt) = ts.next() {
+            let binary_position = after_operand;
+            after_operand = ends_operand(&tt);
             match tt {
+                Punct(p) if p.as_char() == '|' && (closure_params || starts_closure(&b)) => {
+                    b.push('|');
+                    if closure_params {
+                        closure_params = false;
+                    } else if p.spacing() == Spacing::Joint
+                        && matches!(ts.peek(), Some(Punct(p)) if p.as_char() == '|')
+                    {
+                        // A closure with no parameters, `||`.
+                        b.push('|');
+                        ts.next();
+                    } else {
+                        closure_params = true;
+                        continue;
+                    }
+                    if ts.peek().is_some() {
+                        b.push(' ');
+                    }
+                }
                 Punct(p) => {
                     let pc = p.as_char();
+                    // An arithmetic operator after an operand is a binary operator, and
+                    // gets a space after it too.
+                    let binary_op =
+                        binary_position && is_arithmetic_op(pc) && p.spacing() == Spacing::Alone;
+                    // Bounds in a type, like `dyn Future<Output = T> + Send`.
+                    let bounds_plus = pc == '+' && b.ends_with('>');
+                    if bounds_plus || (binary_op && !b.ends_with(' ')) {
+                        b.push(' ');
+                    }
                     b.push(pc);
-                    if ts.peek().is_some() && (b.ends_with("->") || pc == ',' || pc == ';') {
+                    if ts.peek().is_some()
+                        && (b.ends_with("->")
+                            || pc == ','
+                            || pc == ';'
+                            || binary_op
+                            || bounds_plus
+                            || (pc == ':' && p.spacing() == Spacing::Alone && !b.ends_with("::")))
+                    {
                         b.push(' ');
                     }
                 }
@@ -49,6 +90,7 @@ where
                             Ident(_) | Literal(_) => b.push(' '),
                             Punct(p) => match p.as_char() {
                                 ',' | ';' | '<' | '>' | ':' | '.' | '!' => (),
+                                '|' if closure_params => (),
                                 _ => b.push(' '),
                             },
                             Group(_) => (),
@@ -57,7 +99,13 @@ where
                 }
                 Group(g) => {
                     match g.delimiter() {
-                        Delimiter::Brace => b.push('{'),
+                        Delimiter::Brace => {
+                            // A block after an expression, like `match x {`.
+                            if b.ends_with(|c: char| c.is_alphanumeric() || c == ')') {
+                                b.push(' ');
+                            }
+                            b.push('{')
+                        }
                         Delimiter::Bracket => b.push('['),
                         Delimiter::Parenthesis => b.push('('),
                         Delimiter::None => (),
@@ -69,6 +117,10 @@ where
                         Delimiter::Parenthesis => b.push(')'),
                         Delimiter::None => (),
                     }
+                    if matches!(ts.peek(), Some(Punct(p)) if is_arithmetic_op(p.as_char()) || p.as_char() == '=')
+                    {
+                        b.push(' ');
+                    }
                 }
             }
         }
@@ -81,6 +133,29 @@ where
     }
 }
 
+/// True if a `|` following this text would start a closure, rather than being
+/// a binary operator.
+fn starts_closure(b: &str) -> bool {
+    b.is_empty() || b.ends_with(['(', '[', '{']) || b.ends_with(", ") || b.ends_with("= ")
+}
+
+/// True if this token can be the last token of an operand, like `a`, `1`, `f()`, or
+/// `x?`, rather than an operator, separator, or keyword like `return`.
+fn ends_operand(tt: &TokenTree) -> bool {
+    match tt {
+        TokenTree::Ident(ident) => !matches!(
+            ident.to_string().as_str(),
+            "return" | "break" | "in" | "if" | "else" | "match" | "while" | "let" | "move" | "mut"
+        ),
+        TokenTree::Literal(_) | TokenTree::Group(_) => true,
+        TokenTree::Punct(p) => p.as_char() == '?',
+    }
+}
+
+fn is_arithmetic_op(c: char) -> bool {
+    matches!(c, '+' | '-' | '*' | '/' | '%')
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -111,10 +186,57 @@ mod test {
             quote! { impl Iterator < Item = String > }.to_pretty_string(),
             "impl Iterator<Item = String>"
         );
+        assert_eq!(
+            quote! { Box<dyn Future<Output = u8> + Send + 'a> }.to_pretty_string(),
+            "Box<dyn Future<Output = u8> + Send + 'a>"
+        );
     }
 
     #[test]
     fn format_thick_arrow() {
         assert_eq!(quote! { a => b }.to_pretty_string(), "a => b");
     }
+
+    #[test]
+    fn format_arithmetic() {
+        assert_eq!(quote! { a.len() - 1 }.to_pretty_string(), "a.len() - 1");
+        assert_eq!(quote! { n + 1 }.to_pretty_string(), "n + 1");
+        assert_eq!(quote! { Some(-1) }.to_pretty_string(), "Some(-1)");
+        assert_eq!(quote! { -1.0 }.to_pretty_string(), "-1.0");
+        assert_eq!(quote! { f()? + 1 }.to_pretty_string(), "f()? + 1");
+    }
+
+    #[test]
+    fn format_negative_numbers() {
+        assert_eq!(quote! { (-1, -1) }.to_pretty_string(), "(-1, -1)");
+        assert_eq!(quote! { [0, -2] }.to_pretty_string(), "[0, -2]");
+        assert_eq!(quote! { return -1 }.to_pretty_string(), "return -1");
+        assert_eq!(quote! { a - -1 }.to_pretty_string(), "a - -1");
+    }
+
+    #[test]
+    fn format_field_colon() {
+        assert_eq!(
+            quote! { name: Default::default() }.to_pretty_string(),
+            "name: Default::default()"
+        );
+    }
+
+    #[test]
+    fn format_closures() {
+        assert_eq!(quote! { |_, _| true }.to_pretty_string(), "|_, _| true");
+        assert_eq!(quote! { || None }.to_pretty_string(), "|| None");
+        assert_eq!(
+            quote! { f(|x| x + 1, y) }.to_pretty_string(),
+            "f(|x| x + 1, y)"
+        );
+    }
+
+    #[test]
+    fn format_match() {
+        assert_eq!(
+            quote! { match f(x) { Ok(v) => v, Err(_) => return None } }.to_pretty_string(),
+            "match f(x) {Ok(v) => v, Err(_) => return None}"
+        );
+    }
 }
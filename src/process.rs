@@ -15,7 +15,7 @@ use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context};
 use camino::Utf8Path;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use subprocess::{ExitStatus, Popen, PopenConfig, Redirection};
 use tracing::{debug, debug_span, error, span, trace, warn, Level};
 
@@ -194,7 +194,7 @@ fn terminate_child_impl(child: &mut Popen) -> Result<()> {
 }
 
 /// The result of running a single child process.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProcessStatus {
     /// Exited with status 0.
     Success,
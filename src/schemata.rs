@@ -0,0 +1,475 @@
+// Copyright 2024 Martin Pool
+
+//! Build many mutants together, choosing which one is active at runtime.
+//!
+//! This approach is sometimes called "mutant schemata". Each function containing
+//! mutants is rewritten once, so that its body contains one copy of the original body
+//! for each mutant, with that mutant applied, and a final unmutated copy. The copy
+//! that runs is chosen by the [ACTIVE_MUTANT_ENV] environment variable.
+//!
+//! The package can then be built once, and only the tests need to be run for each
+//! mutant, which can be much faster than building every mutant separately.
+//!
+//! Some mutants won't compile when combined this way, for example because their
+//! function returns an `impl Trait` whose concrete type differs between branches.
+//! The functions containing errors are removed from the schemata and their mutants
+//! are tested one at a time in the normal way.
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use anyhow::{ensure, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use itertools::Itertools;
+use syn::visit::Visit;
+use syn::{Block, ImplItemFn, ItemFn, Signature, TraitItemFn};
+use tracing::{debug, error, trace};
+
+use crate::build_dir::BuildDir;
+use crate::mutate::{Genre, Mutant};
+use crate::package::Package;
+use crate::source::SourceFile;
+use crate::span::Span;
+use crate::{Result, MUTATION_MARKER_COMMENT};
+
+/// The environment variable that selects the active mutant, by its number.
+///
+/// If it is unset, or doesn't match any mutant, the original code runs.
+pub const ACTIVE_MUTANT_ENV: &str = "CARGO_MUTANTS_ACTIVE";
+
+/// Return a function inserted into each rewritten body to check whether a mutant is active.
+///
+/// The variable is read only once per function, so that mutants in hot loops aren't
+/// slowed down too much.
+fn active_fn() -> String {
+    format!(
+        "fn cargo_mutants_active(id: usize) -> bool {{ \
+        static ACTIVE: ::std::sync::OnceLock<Option<usize>> = ::std::sync::OnceLock::new(); \
+        *ACTIVE.get_or_init(|| ::std::env::var({ACTIVE_MUTANT_ENV:?}).ok().and_then(|v| v.parse().ok())) \
+        == Some(id) }}"
+    )
+}
+
+/// Mutants within one function body, that are written into the source together.
+#[derive(Debug)]
+struct Group {
+    source_file: SourceFile,
+    /// The span of the function body, including its braces.
+    body: Span,
+    /// Mutants in this body, with their id numbers.
+    mutants: Vec<(usize, Mutant)>,
+}
+
+/// Mutants from one package, that can be built together.
+#[derive(Debug)]
+pub struct Schemata {
+    pub package: Arc<Package>,
+    groups: Vec<Group>,
+}
+
+/// Combine mutants into schemata, one per package.
+///
+/// Mutants are numbered by their position in `mutants`.
+///
+/// Returns the schemata, and the mutants that can't be combined and so should be
+/// tested one at a time: for example, those outside of any function body.
+pub fn schemata(mutants: Vec<Mutant>) -> (Vec<Schemata>, Vec<Mutant>) {
+    let mut bodies_by_file: HashMap<Utf8PathBuf, Vec<Span>> = HashMap::new();
+    let mut all_schemata: Vec<Schemata> = Vec::new();
+    let mut unsuitable = Vec::new();
+    for (id, mutant) in mutants.into_iter().enumerate() {
+        let bodies = bodies_by_file
+            .entry(mutant.source_file.tree_relative_path.clone())
+            .or_insert_with(|| function_bodies(mutant.source_file.code()));
+        let Some(body) = bodies
+            .iter()
+            .find(|body| body.contains(&mutant.span))
+            .copied()
+        else {
+            trace!(?mutant, "Mutant is not in a function body; not combining");
+            unsuitable.push(mutant);
+            continue;
+        };
+        if !combinable(&mutant) {
+            trace!(?mutant, "Mutant can't be combined with others");
+            unsuitable.push(mutant);
+            continue;
+        }
+        let package_name = mutant.package_name();
+        let schemata = match all_schemata
+            .iter_mut()
+            .position(|s| s.package.name == package_name)
+        {
+            Some(i) => &mut all_schemata[i],
+            None => {
+                all_schemata.push(Schemata {
+                    package: Arc::clone(&mutant.source_file.package),
+                    groups: Vec::new(),
+                });
+                all_schemata.last_mut().unwrap()
+            }
+        };
+        match schemata.groups.iter_mut().find(|group| {
+            group.body == body
+                && group.source_file.tree_relative_path == mutant.source_file.tree_relative_path
+        }) {
+            Some(group) => group.mutants.push((id, mutant)),
+            None => schemata.groups.push(Group {
+                source_file: mutant.source_file.clone(),
+                body,
+                mutants: vec![(id, mutant)],
+            }),
+        }
+    }
+    (all_schemata, unsuitable)
+}
+
+/// True if this mutant can be built in a schema alongside others.
+fn combinable(mutant: &Mutant) -> bool {
+    // Replacing the body of a function returning `impl Trait` will often change its concrete
+    // type, which can't differ between the branches.
+    !(mutant.genre == Genre::FnValue
+        && mutant
+            .function
+            .as_ref()
+            .is_some_and(|f| f.return_type.contains("impl ")))
+}
+
+impl Schemata {
+    /// All the mutants in this schemata, with their id numbers.
+    pub fn mutants(&self) -> impl Iterator<Item = &(usize, Mutant)> {
+        self.groups.iter().flat_map(|group| group.mutants.iter())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Return all the mutants, consuming the schemata.
+    pub fn into_mutants(self) -> Vec<Mutant> {
+        self.groups
+            .into_iter()
+            .flat_map(|group| group.mutants.into_iter().map(|(_id, mutant)| mutant))
+            .collect()
+    }
+
+    /// Write the combined source into a build directory.
+    ///
+    /// The original source is restored when the returned [AppliedSchemata] is dropped.
+    pub fn apply<'a>(&self, build_dir: &'a BuildDir) -> Result<AppliedSchemata<'a>> {
+        let mut applied = AppliedSchemata {
+            build_dir,
+            files: Vec::new(),
+        };
+        for (tree_relative_path, groups) in &self
+            .groups
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, group)| &group.source_file.tree_relative_path)
+            .group_by(|(_, group)| &group.source_file.tree_relative_path)
+        {
+            let groups = groups.collect_vec();
+            let original = Arc::clone(&groups[0].1.source_file.code);
+            let (code, regions) = rewrite(
+                &original,
+                &groups.iter().map(|(_, group)| *group).collect_vec(),
+            );
+            let path = build_dir.path().join(tree_relative_path);
+            // for safety, don't follow symlinks
+            ensure!(path.is_file(), "{path:?} is not a file");
+            applied.files.push(SchemaFile {
+                tree_relative_path: tree_relative_path.clone(),
+                original,
+                group_lines: groups.iter().map(|(i, _)| *i).zip(regions).collect(),
+            });
+            fs::write(&path, code.as_bytes())
+                .with_context(|| format!("failed to write schemata to {path:?}"))?;
+        }
+        Ok(applied)
+    }
+
+    /// Remove the function bodies with these indexes, returning their mutants.
+    pub fn remove_groups(&mut self, indexes: &[usize]) -> Vec<Mutant> {
+        let mut removed = Vec::new();
+        let mut i = 0;
+        self.groups.retain_mut(|group| {
+            let keep = !indexes.contains(&i);
+            i += 1;
+            if !keep {
+                removed.extend(group.mutants.drain(..).map(|(_id, mutant)| mutant));
+            }
+            keep
+        });
+        removed
+    }
+}
+
+/// A rewritten source file, and the lines occupied by each rewritten body.
+#[derive(Debug)]
+struct SchemaFile {
+    tree_relative_path: Utf8PathBuf,
+    original: Arc<String>,
+    /// The index of each group, and the lines that it occupies in the rewritten file.
+    group_lines: Vec<(usize, RangeInclusive<usize>)>,
+}
+
+/// Manages the lifetime of schemata written into a build directory; when dropped, the
+/// original source is restored.
+#[must_use]
+pub struct AppliedSchemata<'a> {
+    build_dir: &'a BuildDir,
+    files: Vec<SchemaFile>,
+}
+
+impl AppliedSchemata<'_> {
+    /// Find the function bodies containing compiler errors, from the output of cargo.
+    ///
+    /// Returns their indexes, to pass to [Schemata::remove_groups].
+    pub fn groups_with_errors(&self, cargo_output: &str) -> Vec<usize> {
+        error_locations(cargo_output)
+            .into_iter()
+            .flat_map(|(path, line)| {
+                self.files
+                    .iter()
+                    .filter(move |file| same_file(&file.tree_relative_path, &path))
+                    .flat_map(|file| file.group_lines.iter())
+                    .filter(move |(_, lines)| lines.contains(&line))
+                    .map(|(i, _)| *i)
+            })
+            .unique()
+            .sorted()
+            .collect()
+    }
+}
+
+impl Drop for AppliedSchemata<'_> {
+    fn drop(&mut self) {
+        for file in &self.files {
+            let path = self.build_dir.path().join(&file.tree_relative_path);
+            if let Err(err) = fs::write(&path, file.original.as_bytes()) {
+                error!("Failed to restore {path:?} after schemata: {err}");
+            }
+        }
+    }
+}
+
+/// Rewrite the source of a file so that each of the given function bodies contains all
+/// its mutants.
+///
+/// Returns the new source, and the range of lines occupied by each rewritten body.
+fn rewrite(code: &str, groups: &[&Group]) -> (String, Vec<RangeInclusive<usize>>) {
+    // Replace from the end, so that earlier spans are still valid.
+    let mut new_code = code.to_owned();
+    let mut new_bodies = Vec::new();
+    for group in groups
+        .iter()
+        .sorted_by_key(|group| (group.body.start.line, group.body.start.column))
+        .rev()
+    {
+        let new_body = rewrite_body(code, group);
+        new_code = group.body.replace(&new_code, &new_body);
+        new_bodies.push((group.body, new_body));
+    }
+    // Now work forward to see which lines each body ended up on.
+    let mut added_lines: isize = 0;
+    let mut lines_by_body = new_bodies
+        .into_iter()
+        .rev()
+        .map(|(body, new_body)| {
+            let start = (body.start.line as isize + added_lines) as usize;
+            let new_len = new_body.lines().count();
+            added_lines += new_len as isize - (body.end.line - body.start.line + 1) as isize;
+            (body, start..=(start + new_len - 1))
+        })
+        .collect_vec();
+    let regions = groups
+        .iter()
+        .map(|group| {
+            let i = lines_by_body
+                .iter()
+                .position(|(body, _)| *body == group.body)
+                .expect("body was rewritten");
+            lines_by_body.swap_remove(i).1
+        })
+        .collect();
+    (new_code, regions)
+}
+
+/// Make the new text of one function body, containing each mutant switched on by its id.
+fn rewrite_body(code: &str, group: &Group) -> String {
+    let original = group.body.extract(code);
+    let mut r = format!("{{\n{}\n", active_fn());
+    for (id, mutant) in &group.mutants {
+        let span = mutant.span.relative_to(group.body.start);
+        let mutated = span.replace(
+            &original,
+            &format!("{} {}", &mutant.replacement, MUTATION_MARKER_COMMENT),
+        );
+        r.push_str(&format!("if cargo_mutants_active({id}) {mutated} else "));
+    }
+    r.push_str(&original);
+    r.push_str("\n}");
+    r
+}
+
+/// Find the spans of the bodies of all non-const functions in a file, not including
+/// functions nested inside other functions.
+fn function_bodies(code: &str) -> Vec<Span> {
+    let file = match syn::parse_file(code) {
+        Ok(file) => file,
+        Err(err) => {
+            debug!(?err, "Failed to parse file to find function bodies");
+            return Vec::new();
+        }
+    };
+    let mut visitor = BodyVisitor::default();
+    visitor.visit_file(&file);
+    visitor.bodies
+}
+
+#[derive(Default)]
+struct BodyVisitor {
+    bodies: Vec<Span>,
+}
+
+impl BodyVisitor {
+    fn body(&mut self, sig: &Signature, block: &Block) {
+        // `const fn` can't read the environment, so they can't use schemata.
+        if sig.constness.is_none() {
+            self.bodies.push(block.brace_token.span.join().into());
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for BodyVisitor {
+    fn visit_item_fn(&mut self, i: &'ast ItemFn) {
+        self.body(&i.sig, &i.block);
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
+        self.body(&i.sig, &i.block);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast TraitItemFn) {
+        if let Some(block) = &i.default {
+            self.body(&i.sig, block);
+        }
+    }
+}
+
+/// Find the source locations of errors in cargo's human-readable output.
+///
+/// Returns a list of file paths and line numbers.
+fn error_locations(cargo_output: &str) -> Vec<(String, usize)> {
+    let mut in_error = false;
+    let mut locations = Vec::new();
+    for line in cargo_output.lines() {
+        if line.starts_with("error") {
+            in_error = true;
+        } else if line.starts_with("warning") {
+            in_error = false;
+        } else if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if in_error {
+                in_error = false;
+                let mut parts = location.rsplitn(3, ':');
+                let (_column, line, path) = (parts.next(), parts.next(), parts.next());
+                if let (Some(path), Some(Ok(line))) = (path, line.map(str::parse)) {
+                    locations.push((path.to_owned(), line));
+                }
+            }
+        }
+    }
+    locations
+}
+
+/// True if a path from compiler output refers to this file in the tree.
+///
+/// The compiler might print the path relative to the workspace or the package, so
+/// check whether one is a suffix of the other.
+fn same_file(tree_relative_path: &Utf8Path, reported: &str) -> bool {
+    let reported = Utf8Path::new(reported);
+    tree_relative_path.ends_with(reported) || reported.ends_with(tree_relative_path)
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::console::Console;
+    use crate::options::Options;
+    use crate::workspace::{PackageFilter, Workspace};
+
+    #[test]
+    fn rewrite_function_with_mutants() {
+        let workspace = Workspace::open("testdata/small_well_tested").unwrap();
+        let mutants = workspace
+            .mutants(&PackageFilter::All, &Options::default(), &Console::new())
+            .unwrap();
+        assert_eq!(mutants.len(), 4);
+        let (all_schemata, unsuitable) = schemata(mutants);
+        assert!(unsuitable.is_empty());
+        assert_eq!(all_schemata.len(), 1);
+        let schemata = &all_schemata[0];
+        assert_eq!(
+            schemata.mutants().map(|(id, _)| *id).collect_vec(),
+            [0, 1, 2, 3]
+        );
+        let group = &schemata.groups[0];
+        let code = group.source_file.code();
+        let (new_code, regions) = rewrite(code, &[group]);
+        assert_eq!(regions, [4..=29]);
+        let new_body = new_code.lines().skip(3).take(10).join("\n");
+        assert_eq!(
+            new_body.replace(&active_fn(), "ACTIVE_FN"),
+            indoc! {"
+                pub fn factorial(n: u32) -> u32 {
+                ACTIVE_FN
+                if cargo_mutants_active(0) {
+                    0 /* ~ changed by cargo-mutants ~ */
+                } else if cargo_mutants_active(1) {
+                    1 /* ~ changed by cargo-mutants ~ */
+                } else if cargo_mutants_active(2) {
+                    let mut a = 1;
+                    for i in 2..=n {
+                        a += /* ~ changed by cargo-mutants ~ */ i;"}
+        );
+    }
+
+    #[test]
+    fn find_error_locations() {
+        let output = indoc! {r#"
+            warning: unused variable: `x`
+              --> src/lib.rs:3:9
+            error[E0308]: mismatched types
+               --> src/lib.rs:12:5
+                |
+            12  |     "hello"
+                |     ^^^^^^^ expected `u32`, found `&str`
+            error: could not compile `foo` (lib) due to 1 previous error
+        "#};
+        assert_eq!(error_locations(output), [("src/lib.rs".to_owned(), 12)]);
+    }
+
+    #[test]
+    fn find_function_bodies() {
+        let code = indoc! {r#"
+            fn one() -> u32 {
+                fn nested() {}
+                1
+            }
+            const fn two() -> u32 { 2 }
+            struct S;
+            impl S {
+                fn three(&self) -> u32 { 3 }
+            }
+        "#};
+        assert_eq!(
+            function_bodies(code),
+            [Span::quad(1, 17, 4, 2), Span::quad(8, 28, 8, 33)]
+        );
+    }
+}
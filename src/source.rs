@@ -116,6 +116,7 @@ mod test {
             &Arc::new(Package {
                 name: "imaginary-package".to_owned(),
                 relative_manifest_path: "whatever/Cargo.toml".into(),
+                path_dependency_dirs: Vec::new(),
             }),
             true,
         )
@@ -132,6 +133,7 @@ mod test {
             &Arc::new(Package {
                 name: "imaginary-package".to_owned(),
                 relative_manifest_path: "whatever/Cargo.toml".into(),
+                path_dependency_dirs: Vec::new(),
             }),
             true,
         )
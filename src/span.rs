@@ -11,7 +11,7 @@ use std::fmt;
 use serde::Serialize;
 
 /// A (line, column) position in a source file.
-#[derive(Clone, Copy, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Serialize)]
 pub struct LineColumn {
     /// 1-based line number.
     pub line: usize,
@@ -36,7 +36,7 @@ impl fmt::Debug for LineColumn {
 }
 
 /// A contiguous text span in a file.
-#[derive(Clone, Copy, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Serialize)]
 pub struct Span {
     /// The *inclusive* position where the span starts.
     pub start: LineColumn,
@@ -131,6 +131,29 @@ impl Span {
         }
         r
     }
+
+    /// True if `other` is entirely within this span.
+    pub fn contains(&self, other: &Span) -> bool {
+        let key = |lc: LineColumn| (lc.line, lc.column);
+        key(self.start) <= key(other.start) && key(other.end) <= key(self.end)
+    }
+
+    /// Convert this span within a file to a span within a region of the file that
+    /// starts at `origin`.
+    pub fn relative_to(&self, origin: LineColumn) -> Span {
+        let relative = |lc: LineColumn| LineColumn {
+            line: lc.line - origin.line + 1,
+            column: if lc.line == origin.line {
+                lc.column - origin.column + 1
+            } else {
+                lc.column
+            },
+        };
+        Span {
+            start: relative(self.start),
+            end: relative(self.end),
+        }
+    }
 }
 
 impl From<proc_macro2::Span> for Span {
@@ -193,6 +216,27 @@ mod test {
         assert_eq!(format!("{:?}", span), "Span(1, 2, 3, 4)");
     }
 
+    #[test]
+    fn span_relative_to_region() {
+        let origin = LineColumn {
+            line: 3,
+            column: 10,
+        };
+        assert_eq!(
+            Span::quad(3, 12, 5, 4).relative_to(origin),
+            Span::quad(1, 3, 3, 4)
+        );
+    }
+
+    #[test]
+    fn span_contains_span() {
+        let outer = Span::quad(1, 17, 4, 2);
+        assert!(outer.contains(&Span::quad(2, 5, 2, 6)));
+        assert!(outer.contains(&outer));
+        assert!(!outer.contains(&Span::quad(1, 1, 2, 6)));
+        assert!(!outer.contains(&Span::quad(4, 1, 5, 1)));
+    }
+
     #[test]
     fn cut_before_crlf() {
         let source = "fn foo() {\r\n    wibble();\r\n}\r\n//hey!\r\n";
@@ -7,19 +7,29 @@
 //! e.g. for cargo they are identified from the targets. The tree walker then
 //! follows `mod` statements to recursively visit other referenced files.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::vec;
 
+use itertools::Itertools;
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
 use syn::ext::IdentExt;
+use syn::parse_quote;
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::visit::Visit;
-use syn::{Attribute, BinOp, Block, Expr, File, ItemFn, ReturnType, Signature, UnOp};
+use syn::{
+    Attribute, BinOp, Block, Expr, File, ItemFn, Lit, Meta, RangeLimits, ReturnType, Signature,
+    Token, UnOp,
+};
 use tracing::{debug, debug_span, error, trace, trace_span, warn};
 
-use crate::fnvalue::return_type_replacements;
+use crate::fnvalue::{
+    drop_error_replacement, return_type_replacements, value_replacements, Imports, LocalTypes,
+    ReplacementContext, ReplacementRule, TypeParams,
+};
 use crate::mutate::Function;
 use crate::pretty::ToPrettyString;
 use crate::source::SourceFile;
@@ -33,8 +43,30 @@ use crate::*;
 pub struct Discovered {
     pub mutants: Vec<Mutant>,
     pub files: Vec<SourceFile>,
+    /// Functions returning a generic type parameter that no replacement value could
+    /// be made for, described by their location, name, and return type.
+    pub skipped_generic_fns: Vec<String>,
 }
 
+/// Pairs of well-known methods with the same signature but opposite meanings,
+/// which are swapped for each other by the [Genre::MethodSwap] genre.
+const METHOD_SWAPS: &[(&str, &str)] = &[
+    ("min", "max"),
+    ("first", "last"),
+    ("push_front", "push_back"),
+    ("starts_with", "ends_with"),
+    ("saturating_add", "saturating_sub"),
+    ("is_some", "is_none"),
+    ("is_ok", "is_err"),
+    ("any", "all"),
+    ("take", "skip"),
+];
+
+/// Methods in [METHOD_SWAPS] that are only swapped when they're called with exactly one
+/// argument, because methods of the same name with no arguments mean something else:
+/// `Iterator::take(n)` can be swapped with `skip(n)`, but `Option::take()` can't.
+const ONE_ARG_METHODS: &[&str] = &["take", "skip"];
+
 /// Discover all mutants and all source files.
 ///
 /// The list of source files includes even those with no mutants.
@@ -50,14 +82,34 @@ pub fn walk_tree(
         .iter()
         .map(|e| syn::parse_str(e).with_context(|| format!("Failed to parse error value {e:?}")))
         .collect::<Result<Vec<Expr>>>()?;
+    let replacement_rules = options
+        .replacements
+        .iter()
+        .map(|(pattern, exprs)| ReplacementRule::new(pattern, exprs))
+        .collect::<Result<Vec<ReplacementRule>>>()?;
+    // Types are looked up only in the package of the file being visited, since other
+    // packages might have different types of the same name.
+    let mut local_types: HashMap<String, LocalTypes> = HashMap::new();
     console.walk_tree_start();
     let mut file_queue: VecDeque<SourceFile> = top_source_files.iter().cloned().collect();
-    let mut mutants = Vec::new();
     let mut files: Vec<SourceFile> = Vec::new();
+    let mut syn_files: Vec<File> = Vec::new();
+    // Types can be defined in any file, including ones visited after the functions
+    // that return them, so first parse all the files and find the types and modules
+    // they define, and then look for mutants.
     while let Some(source_file) = file_queue.pop_front() {
-        console.walk_tree_update(files.len(), mutants.len());
+        console.walk_tree_update(files.len(), 0);
         check_interrupted()?;
-        let (mut file_mutants, external_mods) = walk_file(&source_file, &error_exprs)?;
+        let syn_file = syn::parse_str::<File>(source_file.code())
+            .with_context(|| format!("failed to parse {}", source_file.tree_relative_slashes()))?;
+        let FileDefinitions {
+            external_mods,
+            local_types: file_local_types,
+        } = find_definitions(&source_file, &syn_file);
+        local_types
+            .entry(source_file.package.name.clone())
+            .or_default()
+            .extend(file_local_types);
         // We'll still walk down through files that don't match globs, so that
         // we have a chance to find modules underneath them. However, we won't
         // collect any mutants from them, and they don't count as "seen" for
@@ -85,8 +137,37 @@ pub fn walk_tree(
                 continue;
             }
         }
-        mutants.append(&mut file_mutants);
         files.push(source_file);
+        syn_files.push(syn_file);
+    }
+    let local_types: HashMap<String, Arc<LocalTypes>> = local_types
+        .into_iter()
+        .map(|(package_name, types)| {
+            debug!(
+                package_name,
+                n_types = types.names.len(),
+                "Found local types"
+            );
+            (package_name, Arc::new(types))
+        })
+        .collect();
+    let mut mutants = Vec::new();
+    let mut skipped_generic_fns = Vec::new();
+    for (i, (source_file, syn_file)) in files.iter().zip(&syn_files).enumerate() {
+        console.walk_tree_update(i, mutants.len());
+        check_interrupted()?;
+        let context = ReplacementContext {
+            error_exprs: error_exprs.clone(),
+            replacement_rules: replacement_rules.clone(),
+            local_types: local_types
+                .get(&source_file.package.name)
+                .cloned()
+                .unwrap_or_default(),
+            imports: Imports::in_file(syn_file),
+        };
+        let file_mutants = walk_file(source_file, syn_file, &context, options);
+        mutants.extend(file_mutants.mutants);
+        skipped_generic_fns.extend(file_mutants.skipped_generic_fns);
     }
     mutants.retain(|m| {
         let name = m.name(true, false);
@@ -94,32 +175,117 @@ pub fn walk_tree(
             && (options.exclude_names.is_empty() || !options.exclude_names.is_match(&name))
     });
     console.walk_tree_done();
-    Ok(Discovered { mutants, files })
+    Ok(Discovered {
+        mutants,
+        files,
+        skipped_generic_fns,
+    })
+}
+
+/// Information about the `impl` block that we're inside.
+struct ImplInfo {
+    /// The last component of the name of the type, like `Foo` for `impl<T> a::Foo<T>`.
+    self_ident: String,
+
+    /// Functions in this impl that take no arguments and return the type, like `new`
+    /// or `empty`.
+    ///
+    /// Functions that are only built under some `cfg`, such as test helpers, and
+    /// `unsafe` functions are not included, since calling them might not compile.
+    constructors: Vec<Ident>,
+}
+
+impl ImplInfo {
+    fn new(self_ident: String, item_impl: &syn::ItemImpl) -> ImplInfo {
+        let constructors = item_impl
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::ImplItem::Fn(syn::ImplItemFn { attrs, sig, .. })
+                    if sig.inputs.is_empty()
+                        && sig.generics.params.is_empty()
+                        && sig.asyncness.is_none()
+                        && sig.unsafety.is_none()
+                        && !attrs
+                            .iter()
+                            .any(|attr| path_is(attr.path(), &["cfg"]) || attr_is_test(attr))
+                        && matches!(&sig.output, ReturnType::Type(_, ty)
+                            if matches!(&**ty, syn::Type::Path(syn::TypePath { qself: None, path })
+                                if path.is_ident("Self") || path.is_ident(&self_ident))) =>
+                {
+                    Some(sig.ident.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        ImplInfo {
+            self_ident,
+            constructors,
+        }
+    }
+}
+
+/// Modules and types defined in one source file.
+struct FileDefinitions {
+    /// The names of modules referenced by `mod` statements that should be visited later.
+    external_mods: Vec<Vec<ModNamespace>>,
+
+    /// Types defined in the file.
+    local_types: LocalTypes,
+}
+
+/// Find the modules and types defined in a source file.
+fn find_definitions(source_file: &SourceFile, syn_file: &File) -> FileDefinitions {
+    let _span = debug_span!("source_file", path = source_file.tree_relative_slashes()).entered();
+    let mut visitor = DefinitionVisitor {
+        source_file,
+        mod_namespace_stack: Vec::new(),
+        external_mods: Vec::new(),
+        local_types: LocalTypes::default(),
+    };
+    visitor.visit_file(syn_file);
+    FileDefinitions {
+        external_mods: visitor.external_mods,
+        local_types: visitor.local_types,
+    }
+}
+
+/// Mutants found in one source file.
+struct FileMutants {
+    mutants: Vec<Mutant>,
+
+    /// Functions returning a generic type that no replacement could be made for.
+    skipped_generic_fns: Vec<String>,
 }
 
 /// Find all possible mutants in a source file.
-///
-/// Returns the mutants found, and the names of modules referenced by `mod` statements
-/// that should be visited later.
 fn walk_file(
     source_file: &SourceFile,
-    error_exprs: &[Expr],
-) -> Result<(Vec<Mutant>, Vec<Vec<ModNamespace>>)> {
+    syn_file: &File,
+    context: &ReplacementContext,
+    options: &Options,
+) -> FileMutants {
     let _span = debug_span!("source_file", path = source_file.tree_relative_slashes()).entered();
     debug!("visit source file");
-    let syn_file = syn::parse_str::<syn::File>(source_file.code())
-        .with_context(|| format!("failed to parse {}", source_file.tree_relative_slashes()))?;
     let mut visitor = DiscoveryVisitor {
-        error_exprs,
-        external_mods: Vec::new(),
+        context,
+        current_impl: None,
+        type_params: TypeParams::default(),
+        genres: &options.genres,
+        method_swaps: &options.method_swaps,
         mutants: Vec::new(),
-        mod_namespace_stack: Vec::new(),
+        mutant_keys: HashSet::new(),
+        skipped_generic_fns: Vec::new(),
         namespace_stack: Vec::new(),
         fn_stack: Vec::new(),
+        try_return_types: Vec::new(),
         source_file: source_file.clone(),
     };
-    visitor.visit_file(&syn_file);
-    Ok((visitor.mutants, visitor.external_mods))
+    visitor.visit_file(syn_file);
+    FileMutants {
+        mutants: visitor.mutants,
+        skipped_generic_fns: visitor.skipped_generic_fns,
+    }
 }
 
 /// Namespace for a module defined in a `mod foo { ... }` block or `mod foo;` statement
@@ -151,25 +317,173 @@ impl ModNamespace {
     }
 }
 
+/// `syn` visitor that finds the modules and types defined in a file.
+///
+/// This is much cheaper than looking for mutants, and is done for every file first,
+/// so that types defined anywhere in the tree can be used in replacements.
+struct DefinitionVisitor<'s> {
+    /// The file being visited.
+    source_file: &'s SourceFile,
+
+    /// The stack of modules namespaces that we're currently inside, from
+    /// visiting `mod foo { ... }` statements.
+    mod_namespace_stack: Vec<ModNamespace>,
+
+    /// The names from `mod foo;` statements that should be visited later,
+    /// namespaced relative to the source file
+    external_mods: Vec<Vec<ModNamespace>>,
+
+    /// Types defined in this file.
+    local_types: LocalTypes,
+}
+
+impl DefinitionVisitor<'_> {
+    /// Remember that a type is defined, and whether it derives `Default`.
+    fn note_type_definition(&mut self, ident: &Ident, attrs: &[Attribute]) {
+        let name = ident.to_string();
+        if attrs.iter().any(attr_may_derive_default) {
+            self.local_types.with_default.insert(name.clone());
+        }
+        self.local_types.names.insert(name);
+    }
+}
+
+impl<'ast> Visit<'ast> for DefinitionVisitor<'_> {
+    fn visit_file(&mut self, i: &'ast File) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        syn::visit::visit_file(self, i);
+    }
+
+    /// Don't look inside excluded functions.
+    fn visit_item_fn(&mut self, i: &'ast ItemFn) {
+        if !attrs_excluded(&i.attrs) {
+            syn::visit::visit_item_fn(self, i);
+        }
+    }
+
+    /// Visit `impl Foo { ... }`, noticing whether it's an implementation of `Default`.
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        if let (Some((_, trait_path, _)), syn::Type::Path(syn::TypePath { qself: None, path })) =
+            (&i.trait_, &*i.self_ty)
+        {
+            if trait_path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "Default")
+            {
+                if let Some(self_ident) = path.segments.last() {
+                    self.local_types
+                        .with_default
+                        .insert(self_ident.ident.to_string());
+                }
+            }
+        }
+        syn::visit::visit_item_impl(self, i);
+    }
+
+    /// Visit `mod foo { ... }` or `mod foo;`.
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let mod_name = node.ident.unraw().to_string();
+        let _span = trace_span!("mod", line = node.mod_token.span.start().line, mod_name).entered();
+        if attrs_excluded(&node.attrs) {
+            trace!("mod excluded by attrs");
+            return;
+        }
+
+        let source_location = Span::from(node.span());
+
+        // Extract path attribute value, if any (e.g. `#[path="..."]`)
+        let path_attribute = match find_path_attribute(&node.attrs) {
+            Ok(path) => path,
+            Err(path_attribute) => {
+                let definition_site = self
+                    .source_file
+                    .format_source_location(source_location.start);
+                error!(?path_attribute, ?definition_site, %mod_name, "invalid filesystem traversal in mod path attribute");
+                return;
+            }
+        };
+        self.local_types.mods.insert(mod_name.clone());
+        let mod_namespace = ModNamespace {
+            name: mod_name,
+            path_attribute,
+            source_location,
+        };
+        self.mod_namespace_stack.push(mod_namespace.clone());
+
+        // If there's no content in braces, then this is a `mod foo;`
+        // statement referring to an external file. We remember the module
+        // name and then later look for the file.
+        if node.content.is_none() {
+            // If we're already inside `mod a { ... }` and see `mod b;` then
+            // remember [a, b] as an external module to visit later.
+            self.external_mods.push(self.mod_namespace_stack.clone());
+        }
+        syn::visit::visit_item_mod(self, node);
+        assert_eq!(self.mod_namespace_stack.pop(), Some(mod_namespace));
+    }
+
+    /// Visit `enum` definitions, to remember their unit variants.
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        self.note_type_definition(&i.ident, &i.attrs);
+        let unit_variants = i
+            .variants
+            .iter()
+            .filter(|v| matches!(v.fields, syn::Fields::Unit) && !attrs_excluded(&v.attrs))
+            .map(|v| v.ident.to_string())
+            .collect_vec();
+        if !unit_variants.is_empty() {
+            self.local_types
+                .add_enum(i.ident.to_string(), unit_variants);
+        }
+        syn::visit::visit_item_enum(self, i);
+    }
+
+    /// Visit `struct` definitions.
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        self.note_type_definition(&i.ident, &i.attrs);
+        syn::visit::visit_item_struct(self, i);
+    }
+
+    /// Visit `union` definitions.
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        self.note_type_definition(&i.ident, &i.attrs);
+        syn::visit::visit_item_union(self, i);
+    }
+}
+
 /// `syn` visitor that recursively traverses the syntax tree, accumulating places
 /// that could be mutated.
 ///
-/// As it walks the tree, it accumulates within itself a list of mutation opportunities,
-/// and other files referenced by `mod` statements that should be visited later.
+/// As it walks the tree, it accumulates within itself a list of mutation opportunities.
 struct DiscoveryVisitor<'o> {
     /// All the mutants generated by visiting the file.
     mutants: Vec<Mutant>,
 
+    /// The span and replacement text of each mutant already generated, so that
+    /// duplicates can be skipped.
+    mutant_keys: HashSet<(Span, String)>,
+
+    /// Functions returning a generic type that no replacement could be made for.
+    skipped_generic_fns: Vec<String>,
+
     /// The file being visited.
     source_file: SourceFile,
 
-    /// The stack of modules namespaces that we're currently inside, from
-    /// visiting `mod foo { ... }` statements.
-    ///
-    /// This is a subsequence of `namespace_stack` (with `#[path="..."]` information),
-    /// containing only elements that form a module path.
-    mod_namespace_stack: Vec<ModNamespace>,
-
     /// The stack of namespaces, loosely defined, that we're inside.
     ///
     /// Basically these are names or strings that can be concatenated with `::`
@@ -185,22 +499,34 @@ struct DiscoveryVisitor<'o> {
     /// there are nested functions.
     fn_stack: Vec<Arc<Function>>,
 
-    /// The names from `mod foo;` statements that should be visited later,
-    /// namespaced relative to the source file
-    external_mods: Vec<Vec<ModNamespace>>,
+    /// Information about the whole tree used to generate replacement values.
+    context: &'o ReplacementContext,
+
+    /// The `impl` block whose functions we're directly inside, if any.
+    current_impl: Option<ImplInfo>,
+
+    /// Generic type parameters in scope, from the enclosing function, `impl`, or trait.
+    type_params: TypeParams,
 
-    /// Parsed error expressions, from the config file or command line.
-    error_exprs: &'o [Expr],
+    /// Genres turned on in addition to the defaults, from the config file or command line.
+    genres: &'o [Genre],
+
+    /// Pairs of methods to swap, from the config file, in addition to [METHOD_SWAPS].
+    method_swaps: &'o [(String, String)],
+
+    /// The return type that `?` would return to, for each function, closure, or async
+    /// block we're inside, or None if it's not known.
+    try_return_types: Vec<Option<ReturnType>>,
 }
 
 impl<'o> DiscoveryVisitor<'o> {
     fn enter_function(
         &mut self,
-        function_name: &Ident,
+        function_name: &str,
         return_type: &ReturnType,
         span: proc_macro2::Span,
     ) -> Arc<Function> {
-        self.namespace_stack.push(function_name.to_string());
+        self.namespace_stack.push(function_name.to_owned());
         let full_function_name = self.namespace_stack.join("::");
         let function = Arc::new(Function {
             function_name: full_function_name,
@@ -208,6 +534,7 @@ impl<'o> DiscoveryVisitor<'o> {
             span: span.into(),
         });
         self.fn_stack.push(Arc::clone(&function));
+        self.try_return_types.push(Some(return_type.clone()));
         function
     }
 
@@ -220,15 +547,119 @@ impl<'o> DiscoveryVisitor<'o> {
             Some(function),
             "Function stack mismatch"
         );
+        self.try_return_types
+            .pop()
+            .expect("Try return type stack should not be empty");
+    }
+
+    /// Visit something where `?` returns to a place of unknown type, like a closure.
+    fn in_unknown_try_scope<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.try_return_types.push(None);
+        f(self);
+        self.try_return_types.pop();
+    }
+
+    /// Visit a closure whose return type is known, and generate mutants that
+    /// replace its body.
+    ///
+    /// The closure is treated as a function named `{closure}` within the enclosing function.
+    fn visit_typed_closure(&mut self, closure: &syn::ExprClosure, return_type: &ReturnType) {
+        if attrs_excluded(&closure.attrs) {
+            return;
+        }
+        if self.fn_stack.is_empty() || closure.asyncness.is_some() {
+            trace!("Closure is not in a function, or is async; not replacing its body");
+            self.in_unknown_try_scope(|v| syn::visit::visit_expr_closure(v, closure));
+            return;
+        }
+        let function = self.enter_function("{closure}", return_type, closure.span());
+        self.collect_closure_mutants(&closure.body, return_type);
+        syn::visit::visit_expr_closure(self, closure);
+        self.leave_function(function);
+    }
+
+    /// Generate mutants that replace the body of a closure with values of its return type.
+    fn collect_closure_mutants(&mut self, body: &Expr, return_type: &ReturnType) {
+        let (body_span, orig_body) = match body {
+            Expr::Block(syn::ExprBlock {
+                block, label: None, ..
+            }) => match function_body_span(block) {
+                Some(span) => (span, block.to_pretty_string()),
+                None => return,
+            },
+            _ => (body.span().into(), body.to_pretty_string()),
+        };
+        for rep in return_type_replacements(return_type, self.context, &self.type_params) {
+            let new_body = if matches!(body, Expr::Block(_)) {
+                quote! { { #rep } }.to_pretty_string()
+            } else {
+                rep.to_pretty_string()
+            };
+            if orig_body == new_body {
+                debug!("Replacement is the same as the closure body; skipping");
+            } else {
+                self.collect_mutant(body_span, rep, Genre::FnValue);
+            }
+        }
+    }
+
+    /// Return the method to swap for this one, if any.
+    fn swapped_method(&self, method: &str, n_args: usize) -> Option<String> {
+        if ONE_ARG_METHODS.contains(&method) && n_args != 1 {
+            return None;
+        }
+        METHOD_SWAPS
+            .iter()
+            .copied()
+            .chain(
+                self.method_swaps
+                    .iter()
+                    .map(|(a, b)| (a.as_str(), b.as_str())),
+            )
+            .find_map(|(a, b)| {
+                if method == a {
+                    Some(b.to_owned())
+                } else if method == b {
+                    Some(a.to_owned())
+                } else {
+                    None
+                }
+            })
     }
 
     /// Record that we generated some mutants.
     fn collect_mutant(&mut self, span: Span, replacement: TokenStream, genre: Genre) {
+        self.collect_mutant_with_short_replaced(span, None, replacement, genre)
+    }
+
+    /// Record a mutant, described by a short summary of the code it replaces.
+    fn collect_mutant_with_short_replaced(
+        &mut self,
+        span: Span,
+        short_replaced: Option<String>,
+        replacement: TokenStream,
+        genre: Genre,
+    ) {
+        if !(genre.is_default() || self.genres.contains(&genre)) {
+            trace!(?genre, "Genre is not enabled; skipping mutant");
+            return;
+        }
+        let replacement = replacement.to_pretty_string();
+        // Different genres can generate the same mutant, for example replacing a
+        // comparison that's also an `if` condition with `true`.
+        if !self.mutant_keys.insert((span, replacement.clone())) {
+            trace!(?genre, "Same mutant was already generated; skipping");
+            return;
+        }
         self.mutants.push(Mutant {
             source_file: self.source_file.clone(),
             function: self.fn_stack.last().cloned(),
             span,
-            replacement: replacement.to_pretty_string(),
+            replacement,
+            short_replaced,
             genre,
         })
     }
@@ -236,13 +667,31 @@ impl<'o> DiscoveryVisitor<'o> {
     fn collect_fn_mutants(&mut self, sig: &Signature, block: &Block) {
         if let Some(function) = self.fn_stack.last().cloned() {
             let body_span = function_body_span(block).expect("Empty function body");
-            let repls = return_type_replacements(&sig.output, self.error_exprs);
+            let repls = self.self_type_replacements(
+                sig,
+                return_type_replacements(&sig.output, self.context, &self.type_params),
+            );
             if repls.is_empty() {
-                debug!(
-                    function_name = function.function_name,
-                    return_type = function.return_type,
-                    "No mutants generated for this return type"
-                );
+                if self.returns_type_param(sig) {
+                    debug!(
+                        function_name = function.function_name,
+                        return_type = function.return_type,
+                        "No mutants generated for a generic return type without a `Default`, `From`, or numeric bound"
+                    );
+                    self.skipped_generic_fns.push(format!(
+                        "{}:{}: {} {}",
+                        self.source_file.tree_relative_slashes(),
+                        function.span.start.line,
+                        function.function_name,
+                        function.return_type,
+                    ));
+                } else {
+                    debug!(
+                        function_name = function.function_name,
+                        return_type = function.return_type,
+                        "No mutants generated for this return type"
+                    );
+                }
             } else {
                 let orig_block = block.to_token_stream().to_pretty_string();
                 for rep in repls {
@@ -268,6 +717,198 @@ impl<'o> DiscoveryVisitor<'o> {
         }
     }
 
+    /// True if the function returns one of the generic type parameters in scope.
+    fn returns_type_param(&self, sig: &Signature) -> bool {
+        matches!(&sig.output, ReturnType::Type(_, ty)
+            if matches!(&**ty, syn::Type::Path(syn::TypePath { qself: None, path })
+                if self.type_params.contains(path)))
+    }
+
+    /// Adjust the replacements for a function in an `impl` that returns `Self`, or the
+    /// type by name.
+    ///
+    /// This adds calls to other constructors in the same `impl` that take no arguments,
+    /// like `Self::empty()`, and unit variants if the type is a local enum. For `Self`,
+    /// `Default::default()` is dropped if the type is defined in this tree without a
+    /// `Default` implementation; types named explicitly are already filtered in the same
+    /// way when their replacements are generated.
+    fn self_type_replacements(
+        &self,
+        sig: &Signature,
+        mut repls: Vec<TokenStream>,
+    ) -> Vec<TokenStream> {
+        let Some(impl_info) = &self.current_impl else {
+            return repls;
+        };
+        let ReturnType::Type(_, return_type) = &sig.output else {
+            return repls;
+        };
+        let syn::Type::Path(syn::TypePath { qself: None, path }) = &**return_type else {
+            return repls;
+        };
+        let returns_self = path.is_ident("Self");
+        if !(returns_self || path.is_ident(&impl_info.self_ident)) {
+            return repls;
+        }
+        let local_types = &self.context.local_types;
+        if returns_self {
+            if let Some(variants) = local_types.enums.get(&impl_info.self_ident) {
+                // Named by the type rather than `Self`, so that they're recognized as
+                // the same as the original code, if the function returns a constant.
+                let self_ident = Ident::new(&impl_info.self_ident, proc_macro2::Span::call_site());
+                repls.extend(variants.iter().map(|variant| {
+                    let variant = Ident::new(variant, proc_macro2::Span::call_site());
+                    quote! { #self_ident::#variant }
+                }));
+            }
+        }
+        if returns_self
+            && (local_types.lacks_default(&impl_info.self_ident)
+                || local_types.enums.contains_key(&impl_info.self_ident))
+        {
+            repls.retain(|rep| rep.to_pretty_string() != "Default::default()");
+        }
+        repls.extend(
+            impl_info
+                .constructors
+                .iter()
+                .filter(|constructor| **constructor != sig.ident)
+                .map(|constructor| quote! { Self::#constructor() }),
+        );
+        repls
+    }
+
+    /// Generate mutants that replace a condition with constant `true` and `false`.
+    fn collect_condition_mutants(&mut self, cond: &Expr) {
+        if matches!(cond, Expr::Let(_)) {
+            trace!("Skip `if let` or `while let` condition");
+            return;
+        }
+        let orig = cond.to_pretty_string();
+        for rep in [quote! { true }, quote! { false }] {
+            if rep.to_pretty_string() != orig {
+                self.collect_mutant(cond.span().into(), rep, Genre::Condition);
+            }
+        }
+    }
+
+    /// Generate mutants that replace the operands of `a && b` or `a || b` with constants,
+    /// to check that each operand independently affects the outcome.
+    fn collect_operand_mutants(&mut self, expr: &syn::ExprBinary) {
+        // `true && b` is equivalent to `b`, so checks that `a` matters. `a && false`
+        // is always false, which would be the same whichever operand was replaced,
+        // so it's only generated once.
+        let (identity, absorbing) = match expr.op {
+            BinOp::And(_) => (quote! { true }, quote! { false }),
+            BinOp::Or(_) => (quote! { false }, quote! { true }),
+            _ => return,
+        };
+        for (operand, rep) in [
+            (&expr.left, &identity),
+            (&expr.right, &identity),
+            (&expr.right, &absorbing),
+        ] {
+            if operand.to_pretty_string() != rep.to_pretty_string() {
+                self.collect_mutant(operand.span().into(), rep.clone(), Genre::Condition);
+            }
+        }
+    }
+
+    /// Generate mutants that empty the `else` blocks of an `if` expression used as
+    /// a statement, including all the `else` blocks in an `else if` chain.
+    fn collect_else_mutants(&mut self, expr_if: &syn::ExprIf) {
+        let Some((_else, else_branch)) = &expr_if.else_branch else {
+            return;
+        };
+        match &**else_branch {
+            Expr::If(else_if) => self.collect_else_mutants(else_if),
+            Expr::Block(syn::ExprBlock { block, attrs, .. })
+                if !block_is_empty(block) && !attrs_excluded(attrs) =>
+            {
+                self.collect_mutant_with_short_replaced(
+                    else_branch.span().into(),
+                    Some("else block".to_owned()),
+                    quote! { {} },
+                    Genre::Branch,
+                );
+            }
+            _ => (),
+        }
+    }
+
+    /// Generate a mutant that deletes a statement calling a function or method,
+    /// like `vec.push(x);`, whose side effects should be observed by the tests.
+    fn collect_statement_mutant(&mut self, stmt: &syn::Stmt, expr: &Expr) {
+        let attrs = match expr {
+            Expr::Call(syn::ExprCall { attrs, .. })
+            | Expr::MethodCall(syn::ExprMethodCall { attrs, .. }) => attrs,
+            _ => return,
+        };
+        if attrs_excluded(attrs) {
+            return;
+        }
+        self.collect_mutant_with_short_replaced(
+            stmt.span().into(),
+            Some(format!("statement {}", expr.to_pretty_string())),
+            quote! {},
+            Genre::Statement,
+        );
+    }
+
+    /// If a function returns `()` and its body ends with an `if` expression, then
+    /// that `if` also has type `()` and its `else` blocks can be emptied.
+    fn collect_tail_else_mutants(&mut self, sig: &Signature, block: &Block) {
+        if let (ReturnType::Default, Some(syn::Stmt::Expr(Expr::If(expr_if), None))) =
+            (&sig.output, block.stmts.last())
+        {
+            self.collect_else_mutants(expr_if);
+        }
+    }
+
+    /// Generate mutants that replace the value of a `const` or `static` item with
+    /// values of its type, and tweak literal values.
+    ///
+    /// These mutants are named after the item, and usually have no enclosing function.
+    fn collect_const_mutants(&mut self, kind: &str, ident: &Ident, ty: &syn::Type, expr: &Expr) {
+        if ident == "_" {
+            // Probably a compile-time assertion.
+            return;
+        }
+        let name = self
+            .namespace_stack
+            .iter()
+            .cloned()
+            .chain([ident.to_string()])
+            .join("::");
+        let short_replaced = format!("{kind} {name}");
+        let orig = expr.to_pretty_string();
+        let literal_reps = match expr {
+            Expr::Lit(syn::ExprLit { lit, .. }) => literal_replacements(lit, Some(ty)),
+            _ => Vec::new(),
+        };
+        for rep in value_replacements(ty, self.context, &self.type_params)
+            .into_iter()
+            .chain(literal_reps)
+        {
+            let rep_text = rep.to_pretty_string();
+            if rep_text == orig {
+                trace!(?rep_text, "Replacement is the same as the value; skipping");
+            } else if !syn::parse2::<Expr>(rep.clone()).is_ok_and(|rep| is_const_expr(&rep)) {
+                trace!(
+                    ?rep_text,
+                    "Replacement can't be evaluated in a constant; skipping"
+                );
+            } else {
+                self.collect_mutant_with_short_replaced(
+                    expr.span().into(),
+                    Some(short_replaced.clone()),
+                    rep,
+                    Genre::Constant,
+                );
+            }
+        }
+    }
+
     /// Call a function with a namespace pushed onto the stack.
     ///
     /// This is used when recursively descending into a namespace.
@@ -306,10 +947,20 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
         if fn_sig_excluded(&i.sig) || attrs_excluded(&i.attrs) || block_is_empty(&i.block) {
             return;
         }
-        let function = self.enter_function(&i.sig.ident, &i.sig.output, i.span());
+        // `Self` and outer generic parameters can't be used in a nested function,
+        // even if it's inside an impl.
+        let outer_impl = self.current_impl.take();
+        let outer_type_params = std::mem::replace(
+            &mut self.type_params,
+            TypeParams::default().with_generics(&i.sig.generics),
+        );
+        let function = self.enter_function(&i.sig.ident.to_string(), &i.sig.output, i.span());
         self.collect_fn_mutants(&i.sig, &i.block);
+        self.collect_tail_else_mutants(&i.sig, &i.block);
         syn::visit::visit_item_fn(self, i);
         self.leave_function(function);
+        self.type_params = outer_type_params;
+        self.current_impl = outer_impl;
     }
 
     /// Visit `fn foo()` within an `impl`.
@@ -330,10 +981,14 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
         {
             return;
         }
-        let function = self.enter_function(&i.sig.ident, &i.sig.output, i.span());
+        let type_params = self.type_params.with_generics(&i.sig.generics);
+        let outer_type_params = std::mem::replace(&mut self.type_params, type_params);
+        let function = self.enter_function(&i.sig.ident.to_string(), &i.sig.output, i.span());
         self.collect_fn_mutants(&i.sig, &i.block);
+        self.collect_tail_else_mutants(&i.sig, &i.block);
         syn::visit::visit_impl_item_fn(self, i);
         self.leave_function(function);
+        self.type_params = outer_type_params;
     }
 
     /// Visit `fn foo() { ... }` within a trait, i.e. a default implementation of a function.
@@ -352,10 +1007,14 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
             if block_is_empty(block) {
                 return;
             }
-            let function = self.enter_function(&i.sig.ident, &i.sig.output, i.span());
+            let type_params = self.type_params.with_generics(&i.sig.generics);
+            let outer_type_params = std::mem::replace(&mut self.type_params, type_params);
+            let function = self.enter_function(&i.sig.ident.to_string(), &i.sig.output, i.span());
             self.collect_fn_mutants(&i.sig, block);
+            self.collect_tail_else_mutants(&i.sig, block);
             syn::visit::visit_trait_item_fn(self, i);
             self.leave_function(function);
+            self.type_params = outer_type_params;
         }
     }
 
@@ -365,6 +1024,12 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
             return;
         }
         let type_name = i.self_ty.to_pretty_string();
+        let self_ident = match &*i.self_ty {
+            syn::Type::Path(syn::TypePath { qself: None, path }) => {
+                path.segments.last().map(|s| s.ident.to_string())
+            }
+            _ => None,
+        };
         let name = if let Some((_, trait_path, _)) = &i.trait_ {
             let trait_name = &trait_path.segments.last().unwrap().ident;
             if trait_name == "Default" {
@@ -375,58 +1040,68 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
         } else {
             type_name
         };
+        let impl_info = self_ident.map(|self_ident| ImplInfo::new(self_ident, i));
+        let outer_impl = std::mem::replace(&mut self.current_impl, impl_info);
+        let outer_type_params = std::mem::replace(
+            &mut self.type_params,
+            TypeParams::default().with_generics(&i.generics),
+        );
         self.in_namespace(&name, |v| syn::visit::visit_item_impl(v, i));
+        self.type_params = outer_type_params;
+        self.current_impl = outer_impl;
     }
 
-    /// Visit `trait Foo { ... }`
-    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
-        let name = i.ident.to_pretty_string();
-        let _span = trace_span!("trait", line = i.span().start().line, name).entered();
+    /// Visit `const X: T = ...;`.
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
         if attrs_excluded(&i.attrs) {
             return;
         }
-        self.in_namespace(&name, |v| syn::visit::visit_item_trait(v, i));
+        self.collect_const_mutants("const", &i.ident, &i.ty, &i.expr);
+        syn::visit::visit_item_const(self, i);
     }
 
-    /// Visit `mod foo { ... }` or `mod foo;`.
-    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
-        let mod_name = node.ident.unraw().to_string();
-        let _span = trace_span!("mod", line = node.mod_token.span.start().line, mod_name).entered();
-        if attrs_excluded(&node.attrs) {
-            trace!("mod excluded by attrs");
+    /// Visit `static X: T = ...;`.
+    fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
+        if attrs_excluded(&i.attrs) {
             return;
         }
+        self.collect_const_mutants("static", &i.ident, &i.ty, &i.expr);
+        syn::visit::visit_item_static(self, i);
+    }
 
-        let source_location = Span::from(node.span());
+    /// Visit `const X: T = ...;` within an `impl`.
+    fn visit_impl_item_const(&mut self, i: &'ast syn::ImplItemConst) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        self.collect_const_mutants("const", &i.ident, &i.ty, &i.expr);
+        syn::visit::visit_impl_item_const(self, i);
+    }
 
-        // Extract path attribute value, if any (e.g. `#[path="..."]`)
-        let path_attribute = match find_path_attribute(&node.attrs) {
-            Ok(path) => path,
-            Err(path_attribute) => {
-                let definition_site = self
-                    .source_file
-                    .format_source_location(source_location.start);
-                error!(?path_attribute, ?definition_site, %mod_name, "invalid filesystem traversal in mod path attribute");
-                return;
-            }
-        };
-        let mod_namespace = ModNamespace {
-            name: mod_name,
-            path_attribute,
-            source_location,
-        };
-        self.mod_namespace_stack.push(mod_namespace.clone());
+    /// Visit `trait Foo { ... }`
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        let name = i.ident.to_pretty_string();
+        let _span = trace_span!("trait", line = i.span().start().line, name).entered();
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        let outer_type_params = std::mem::replace(
+            &mut self.type_params,
+            TypeParams::default().with_generics(&i.generics),
+        );
+        self.in_namespace(&name, |v| syn::visit::visit_item_trait(v, i));
+        self.type_params = outer_type_params;
+    }
 
-        // If there's no content in braces, then this is a `mod foo;`
-        // statement referring to an external file. We remember the module
-        // name and then later look for the file.
-        if node.content.is_none() {
-            // If we're already inside `mod a { ... }` and see `mod b;` then
-            // remember [a, b] as an external module to visit later.
-            self.external_mods.push(self.mod_namespace_stack.clone());
+    /// Visit `mod foo { ... }` or `mod foo;`.
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let mod_name = node.ident.unraw().to_string();
+        let _span = trace_span!("mod", line = node.mod_token.span.start().line, mod_name).entered();
+        if attrs_excluded(&node.attrs) {
+            trace!("mod excluded by attrs");
+            return;
         }
-        self.in_namespace(&mod_namespace.name, |v| syn::visit::visit_item_mod(v, node));
-        assert_eq!(self.mod_namespace_stack.pop(), Some(mod_namespace));
+        self.in_namespace(&mod_name, |v| syn::visit::visit_item_mod(v, node));
     }
 
     /// Visit `a op b` expressions.
@@ -447,10 +1122,10 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
             BinOp::Ne(_) => vec![quote! { == }],
             BinOp::And(_) => vec![quote! { || }],
             BinOp::Or(_) => vec![quote! { && }],
-            BinOp::Lt(_) => vec![quote! { == }, quote! {>}],
-            BinOp::Gt(_) => vec![quote! { == }, quote! {<}],
-            BinOp::Le(_) => vec![quote! {>}],
-            BinOp::Ge(_) => vec![quote! {<}],
+            BinOp::Lt(_) => vec![quote! { == }, quote! {>}, quote! {<=}],
+            BinOp::Gt(_) => vec![quote! { == }, quote! {<}, quote! {>=}],
+            BinOp::Le(_) => vec![quote! {>}, quote! {<}],
+            BinOp::Ge(_) => vec![quote! {<}, quote! {>}],
             BinOp::Add(_) => vec![quote! {-}, quote! {*}],
             BinOp::AddAssign(_) => vec![quote! {-=}, quote! {*=}],
             BinOp::Sub(_) => vec![quote! {+}, quote! {/}],
@@ -482,9 +1157,295 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
         replacements
             .into_iter()
             .for_each(|rep| self.collect_mutant(i.op.span().into(), rep, Genre::BinaryOperator));
+        if matches!(
+            i.op,
+            BinOp::Lt(_) | BinOp::Gt(_) | BinOp::Le(_) | BinOp::Ge(_)
+        ) {
+            // Also replace the whole comparison, to check that it's ever both true and false.
+            for rep in [quote! { true }, quote! { false }] {
+                self.collect_mutant(i.span().into(), rep, Genre::BinaryOperator);
+            }
+        }
+        self.collect_operand_mutants(i);
         syn::visit::visit_expr_binary(self, i);
     }
 
+    /// Visit `if cond { ... }`.
+    fn visit_expr_if(&mut self, i: &'ast syn::ExprIf) {
+        let _span = trace_span!("if", line = i.if_token.span.start().line).entered();
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        self.collect_condition_mutants(&i.cond);
+        syn::visit::visit_expr_if(self, i);
+    }
+
+    /// Visit `while cond { ... }`.
+    fn visit_expr_while(&mut self, i: &'ast syn::ExprWhile) {
+        let _span = trace_span!("while", line = i.while_token.span.start().line).entered();
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        self.collect_condition_mutants(&i.cond);
+        syn::visit::visit_expr_while(self, i);
+    }
+
+    /// Visit a block, looking for statements that can be deleted or changed.
+    fn visit_block(&mut self, i: &'ast Block) {
+        for (n, stmt) in i.stmts.iter().enumerate() {
+            let is_last = n + 1 == i.stmts.len();
+            match stmt {
+                // An expression that's followed by a semicolon, or by other statements,
+                // is a statement whose value is `()`.
+                syn::Stmt::Expr(Expr::If(expr_if), semi)
+                    if (semi.is_some() || !is_last) && !attrs_excluded(&expr_if.attrs) =>
+                {
+                    self.collect_else_mutants(expr_if);
+                }
+                // The last statement is never deleted, because it might be there to
+                // diverge, like `exit(1);`, and without it the block might have the wrong
+                // type.
+                syn::Stmt::Expr(expr, Some(_semi)) if !is_last && !self.fn_stack.is_empty() => {
+                    self.collect_statement_mutant(stmt, expr);
+                }
+                _ => (),
+            }
+        }
+        syn::visit::visit_block(self, i);
+    }
+
+    /// Visit `match` expressions.
+    fn visit_expr_match(&mut self, i: &'ast syn::ExprMatch) {
+        let _span = trace_span!("match", line = i.match_token.span.start().line).entered();
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        // Arms can only be deleted if there's a catch-all `_` arm to take over.
+        if i.arms.iter().any(arm_is_catchall) {
+            for arm in &i.arms {
+                if !arm_is_catchall(arm) && !attrs_excluded(&arm.attrs) {
+                    self.collect_mutant_with_short_replaced(
+                        arm.span().into(),
+                        Some(format!("match arm {}", arm.pat.to_pretty_string())),
+                        quote! {},
+                        Genre::Branch,
+                    );
+                }
+            }
+        } else {
+            trace!("match has no `_` arm; not deleting arms");
+        }
+        syn::visit::visit_expr_match(self, i);
+    }
+
+    /// Visit a match arm, which might have an `if` guard.
+    fn visit_arm(&mut self, i: &'ast syn::Arm) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        if let Some((_if, guard)) = &i.guard {
+            self.collect_condition_mutants(guard);
+        }
+        syn::visit::visit_arm(self, i);
+    }
+
+    /// Visit ranges like `a..b` and `a..=b`.
+    fn visit_expr_range(&mut self, i: &'ast syn::ExprRange) {
+        let _span = trace_span!("range", line = i.limits.span().start().line).entered();
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        if let Some(end) = &i.end {
+            // Swapping the limits is equivalent to moving the end by one in one
+            // direction, so the other mutant moves it in the opposite direction.
+            let (swapped_limits, moved_end) = match i.limits {
+                RangeLimits::HalfOpen(_) => (quote! { ..= }, quote! { #end - 1 }),
+                RangeLimits::Closed(_) => (quote! { .. }, quote! { #end + 1 }),
+            };
+            self.collect_mutant(i.limits.span().into(), swapped_limits, Genre::Range);
+            if expr_is_simple(end) {
+                self.collect_mutant(end.span().into(), moved_end, Genre::Range);
+            }
+        }
+        syn::visit::visit_expr_range(self, i);
+    }
+
+    /// Visit `expr?`.
+    fn visit_expr_try(&mut self, i: &'ast syn::ExprTry) {
+        let _span = trace_span!("try", line = i.question_token.span().start().line).entered();
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        if let Some(Some(return_type)) = self.try_return_types.last() {
+            if let Some(rep) =
+                drop_error_replacement(return_type, &i.expr, self.context, &self.type_params)
+            {
+                // The `match` is parenthesized so that it's still one expression in
+                // positions like `a?.b()` or `f()? + 1;`.
+                self.collect_mutant(i.span().into(), quote! { (#rep) }, Genre::ErrorHandling);
+            }
+        } else {
+            trace!("`?` is not directly inside a function; not dropping error");
+        }
+        syn::visit::visit_expr_try(self, i);
+    }
+
+    /// Visit method calls, looking for `unwrap_or` and similar.
+    fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        let method = i.method.to_string();
+        let replacement = match (method.as_str(), i.args.len()) {
+            ("unwrap_or" | "unwrap_or_else", 1) => Some(quote! { unwrap_or_default() }),
+            // If the tests never reach the default case, this won't panic.
+            ("unwrap_or_default", 0) => Some(quote! { unwrap() }),
+            _ => None,
+        };
+        if let Some(rep) = replacement {
+            let span = Span {
+                start: i.method.span().start().into(),
+                end: i.paren_token.span.close().end().into(),
+            };
+            self.collect_mutant(span, rep, Genre::ErrorHandling);
+        }
+        let state_change = match (method.as_str(), i.args.len()) {
+            // `Option::take` or `Cell::take`, but not `Iterator::take(n)`.
+            ("take", 0) => {
+                let receiver = &i.receiver;
+                Some(quote! { #receiver.clone() })
+            }
+            ("clear", 0) => Some(quote! { () }),
+            _ => None,
+        };
+        if let Some(rep) = state_change {
+            self.collect_mutant(i.span().into(), rep, Genre::StateChange);
+        }
+        if let Some(swapped) = self.swapped_method(&method, i.args.len()) {
+            let swapped = Ident::new(&swapped, i.method.span());
+            self.collect_mutant(
+                i.method.span().into(),
+                quote! { #swapped },
+                Genre::MethodSwap,
+            );
+        }
+        if ["filter", "retain", "any", "all"].contains(&method.as_str()) {
+            // These take a predicate closure that returns bool, even if it's not annotated.
+            self.visit_expr(&i.receiver);
+            for arg in &i.args {
+                match arg {
+                    Expr::Closure(closure) if matches!(closure.output, ReturnType::Default) => {
+                        self.visit_typed_closure(closure, &parse_quote! { -> bool })
+                    }
+                    _ => self.visit_expr(arg),
+                }
+            }
+        } else {
+            syn::visit::visit_expr_method_call(self, i);
+        }
+    }
+
+    /// Visit function calls, looking for `mem::take` and similar.
+    fn visit_expr_call(&mut self, i: &'ast syn::ExprCall) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        if let Some(rep) = state_change_replacement(i) {
+            self.collect_mutant(i.span().into(), rep, Genre::StateChange);
+        }
+        syn::visit::visit_expr_call(self, i);
+    }
+
+    /// Visit struct literals like `Foo { a, b: 1 }`.
+    fn visit_expr_struct(&mut self, i: &'ast syn::ExprStruct) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        if !self.fn_stack.is_empty() {
+            let struct_name = i.path.to_pretty_string();
+            for field in &i.fields {
+                let syn::Member::Named(name) = &field.member else {
+                    continue;
+                };
+                if attrs_excluded(&field.attrs) {
+                    continue;
+                }
+                // Only shorthand fields, and fields with literal values, are replaced,
+                // because in other cases the type of the field might not implement Default.
+                let short_replaced = Some(format!("{struct_name} field {name}"));
+                if field.colon_token.is_none() {
+                    self.collect_mutant_with_short_replaced(
+                        field.span().into(),
+                        short_replaced,
+                        quote! { #name: Default::default() },
+                        Genre::StructField,
+                    );
+                } else if let Expr::Lit(syn::ExprLit { lit, .. }) = &field.expr {
+                    if !lit_is_default(lit) {
+                        self.collect_mutant_with_short_replaced(
+                            field.expr.span().into(),
+                            short_replaced,
+                            quote! { Default::default() },
+                            Genre::StructField,
+                        );
+                    }
+                }
+            }
+        }
+        syn::visit::visit_expr_struct(self, i);
+    }
+
+    /// Visit closures, where `?` returns from the closure.
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        if matches!(i.output, ReturnType::Type(..)) {
+            self.visit_typed_closure(i, &i.output);
+        } else {
+            self.in_unknown_try_scope(|v| syn::visit::visit_expr_closure(v, i));
+        }
+    }
+
+    /// Visit `async { ... }` blocks, where `?` returns from the block.
+    fn visit_expr_async(&mut self, i: &'ast syn::ExprAsync) {
+        self.in_unknown_try_scope(|v| syn::visit::visit_expr_async(v, i));
+    }
+
+    /// Visit literals like `1024` or `"hello"`.
+    fn visit_expr_lit(&mut self, i: &'ast syn::ExprLit) {
+        if self.fn_stack.is_empty() || attrs_excluded(&i.attrs) {
+            return;
+        }
+        for rep in literal_replacements(&i.lit, None) {
+            self.collect_mutant(i.lit.span().into(), rep, Genre::Literal);
+        }
+    }
+
+    /// Don't look for literals or anything else inside attributes.
+    fn visit_attribute(&mut self, _i: &'ast Attribute) {}
+
+    /// Don't look inside patterns, where literals can't be changed
+    /// without changing which values are matched.
+    fn visit_pat(&mut self, _i: &'ast syn::Pat) {}
+
+    /// Visit `[x; len]`, but not the length, which must be a constant.
+    fn visit_expr_repeat(&mut self, i: &'ast syn::ExprRepeat) {
+        if attrs_excluded(&i.attrs) {
+            return;
+        }
+        self.visit_expr(&i.expr);
+    }
+
+    /// Visit array types `[T; len]`, but not the length.
+    fn visit_type_array(&mut self, i: &'ast syn::TypeArray) {
+        self.visit_type(&i.elem);
+    }
+
+    /// Visit generic arguments, except for const generics like `f::<3>()`.
+    fn visit_generic_argument(&mut self, i: &'ast syn::GenericArgument) {
+        if !matches!(i, syn::GenericArgument::Const(_)) {
+            syn::visit::visit_generic_argument(self, i);
+        }
+    }
+
     fn visit_expr_unary(&mut self, i: &'ast syn::ExprUnary) {
         let _span = trace_span!("unary", line = i.op.span().start().line).entered();
         trace!("visit unary operator");
@@ -509,6 +1470,218 @@ impl<'ast> Visit<'ast> for DiscoveryVisitor<'_> {
     }
 }
 
+/// Generate a replacement for a call to `mem::take`, `mem::replace`, `mem::swap`,
+/// or `drop`, that leaves the state unchanged.
+///
+/// `take` and `replace` return a clone of the current value instead.
+fn state_change_replacement(call: &syn::ExprCall) -> Option<TokenStream> {
+    let Expr::Path(syn::ExprPath { path, .. }) = &*call.func else {
+        return None;
+    };
+    let idents = path
+        .segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect_vec();
+    let is_mem = idents.len() >= 2 && idents[idents.len() - 2] == "mem";
+    match (idents.last()?.as_str(), call.args.len()) {
+        ("take", 1) | ("replace", 2) if is_mem => {
+            let place = match &call.args[0] {
+                // Places like `self.buf` can be the receiver of `.clone()` as they are,
+                // but others like `*x` need parentheses.
+                Expr::Reference(syn::ExprReference {
+                    mutability: Some(_),
+                    expr,
+                    ..
+                }) if matches!(
+                    &**expr,
+                    Expr::Path(_) | Expr::Field(_) | Expr::Index(_) | Expr::Paren(_)
+                ) =>
+                {
+                    quote! { #expr }
+                }
+                Expr::Reference(syn::ExprReference {
+                    mutability: Some(_),
+                    expr,
+                    ..
+                }) => quote! { (#expr) },
+                arg => quote! { (*#arg) },
+            };
+            Some(quote! { #place.clone() })
+        }
+        ("swap", 2) if is_mem => Some(quote! { () }),
+        ("drop", 1) if is_mem || idents.len() == 1 => Some(quote! { () }),
+        _ => None,
+    }
+}
+
+/// True if the literal is the default value of its type, like `0` or `""`.
+fn lit_is_default(lit: &Lit) -> bool {
+    match lit {
+        Lit::Bool(b) => !b.value,
+        Lit::Int(i) => i.base10_digits() == "0",
+        Lit::Float(f) => f.base10_digits().parse::<f64>() == Ok(0.0),
+        Lit::Str(s) => s.value().is_empty(),
+        _ => false,
+    }
+}
+
+/// True if the expression is simple enough that `expr - 1` is clearly a number one less,
+/// like a variable, a literal, a field, or a call like `v.len()`.
+fn expr_is_simple(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(_) | Expr::Lit(_) => true,
+        Expr::Field(syn::ExprField { base, .. }) => expr_is_simple(base),
+        Expr::MethodCall(syn::ExprMethodCall { receiver, args, .. }) => {
+            args.is_empty() && expr_is_simple(receiver)
+        }
+        Expr::Paren(syn::ExprParen { expr, .. }) => expr_is_simple(expr),
+        _ => false,
+    }
+}
+
+/// Generate replacements for a literal: a different number, an empty string, etc.
+///
+/// Integers `n` are replaced by `0`, `n + 1` and `n - 1`, and similarly for floats,
+/// keeping any type suffix.
+///
+/// `ty` is the declared type of the item that the literal initializes, if known, like
+/// `u8` in `const X: u8 = 255`. Integers stay within the range of the suffix or of
+/// this type, and never go below zero.
+fn literal_replacements(lit: &Lit, ty: Option<&syn::Type>) -> Vec<TokenStream> {
+    let texts: Vec<String> = match lit {
+        Lit::Bool(b) => vec![(!b.value).to_string()],
+        Lit::Int(int) => {
+            let Ok(n) = int.base10_parse::<u128>() else {
+                return Vec::new();
+            };
+            let type_name = match (int.suffix(), ty) {
+                ("", Some(syn::Type::Path(syn::TypePath { qself: None, path }))) => {
+                    path.get_ident().map(Ident::to_string).unwrap_or_default()
+                }
+                (suffix, _) => suffix.to_owned(),
+            };
+            let max = int_literal_max(&type_name);
+            [
+                Some(0),
+                n.checked_add(1).filter(|v| *v <= max),
+                n.checked_sub(1),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|v| *v != n)
+            .map(|v| format!("{v}{}", int.suffix()))
+            .collect()
+        }
+        Lit::Float(float) => {
+            let Ok(x) = float.base10_parse::<f64>() else {
+                return Vec::new();
+            };
+            [0.0, x + 1.0, x - 1.0]
+                .into_iter()
+                .filter(|v| *v != x && v.is_finite())
+                .map(|v| {
+                    // Debug formatting always includes a decimal point or exponent.
+                    let text = format!("{v:?}{}", float.suffix());
+                    // Parenthesize negative numbers, in case they're the receiver of
+                    // a method call, like `0.5.sqrt()`.
+                    if v < 0.0 {
+                        format!("({text})")
+                    } else {
+                        text
+                    }
+                })
+                .collect()
+        }
+        Lit::Str(s) if !s.value().is_empty() => vec![r#""""#.to_owned()],
+        Lit::ByteStr(s) if !s.value().is_empty() => vec![r#"b"""#.to_owned()],
+        Lit::Char(c) if c.value() != '\0' => vec![r"'\0'".to_owned()],
+        _ => Vec::new(),
+    };
+    texts
+        .into_iter()
+        .unique()
+        .map(|text| TokenStream::from_str(&text).expect("parse literal replacement"))
+        .collect()
+}
+
+/// The largest value of an integer type, named by a literal's suffix or a declared type.
+///
+/// Unsuffixed literals whose type isn't known are only limited by what can be parsed.
+fn int_literal_max(type_name: &str) -> u128 {
+    match type_name {
+        "u8" => u8::MAX.into(),
+        "u16" => u16::MAX.into(),
+        "u32" => u32::MAX.into(),
+        "u64" => u64::MAX.into(),
+        "usize" => usize::MAX as u128,
+        "i8" => i8::MAX as u128,
+        "i16" => i16::MAX as u128,
+        "i32" => i32::MAX as u128,
+        "i64" => i64::MAX as u128,
+        "i128" => i128::MAX as u128,
+        "isize" => isize::MAX as u128,
+        _ => u128::MAX,
+    }
+}
+
+/// Functions from the standard library and popular crates that can be called in constant
+/// expressions, and that are used in replacement values.
+const CONST_FNS: &[&str] = &[
+    "String::new",
+    "Vec::new",
+    "Duration::from_secs",
+    "Bytes::new",
+    "Bytes::from_static",
+];
+
+/// True if a replacement expression can be evaluated in a `const` or `static` item.
+///
+/// This is conservative: it accepts literals, paths to constants or unit variants,
+/// tuple struct or variant constructors like `Some(1)`, a few known `const fn`s, and
+/// references, tuples, and arrays of those. Method calls, macros, and trait functions
+/// like `Default::default()` or `From::from` are rejected.
+fn is_const_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) | Expr::Path(_) => true,
+        Expr::Unary(syn::ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        })
+        | Expr::Reference(syn::ExprReference {
+            mutability: None,
+            expr,
+            ..
+        })
+        | Expr::Paren(syn::ExprParen { expr, .. }) => is_const_expr(expr),
+        Expr::Tuple(syn::ExprTuple { elems, .. }) | Expr::Array(syn::ExprArray { elems, .. }) => {
+            elems.iter().all(is_const_expr)
+        }
+        Expr::Repeat(syn::ExprRepeat { expr, .. }) => is_const_expr(expr),
+        Expr::Call(syn::ExprCall { func, args, .. }) => {
+            let Expr::Path(syn::ExprPath { path, .. }) = &**func else {
+                return false;
+            };
+            let is_constructor = path.segments.last().is_some_and(|segment| {
+                segment
+                    .ident
+                    .to_string()
+                    .starts_with(|c: char| c.is_ascii_uppercase())
+            });
+            // The last two segments, like `String::new` from `std::string::String::new`.
+            let name = path
+                .segments
+                .iter()
+                .skip(path.segments.len().saturating_sub(2))
+                .map(|segment| segment.ident.to_string())
+                .join("::");
+            (is_constructor || CONST_FNS.contains(&name.as_str())) && args.iter().all(is_const_expr)
+        }
+        _ => false,
+    }
+}
+
 // Get the span of the block excluding the braces, or None if it is empty.
 fn function_body_span(block: &Block) -> Option<Span> {
     Some(Span {
@@ -619,6 +1792,11 @@ fn attrs_excluded(attrs: &[Attribute]) -> bool {
         .any(|attr| attr_is_cfg_test(attr) || attr_is_test(attr) || attr_is_mutants_skip(attr))
 }
 
+/// True if the match arm is `_ => ...`, with no guard, so it matches everything.
+fn arm_is_catchall(arm: &syn::Arm) -> bool {
+    matches!(arm.pat, syn::Pat::Wild(_)) && arm.guard.is_none()
+}
+
 /// True if the block (e.g. the contents of a function) is empty.
 fn block_is_empty(block: &syn::Block) -> bool {
     block.stmts.is_empty()
@@ -626,7 +1804,7 @@ fn block_is_empty(block: &syn::Block) -> bool {
 
 /// True if the attribute looks like `#[cfg(test)]`, or has "test"
 /// anywhere in it.
-fn attr_is_cfg_test(attr: &Attribute) -> bool {
+pub(crate) fn attr_is_cfg_test(attr: &Attribute) -> bool {
     if !path_is(attr.path(), &["cfg"]) {
         return false;
     }
@@ -648,7 +1826,7 @@ fn attr_is_cfg_test(attr: &Attribute) -> bool {
 }
 
 /// True if the attribute is `#[test]`.
-fn attr_is_test(attr: &Attribute) -> bool {
+pub(crate) fn attr_is_test(attr: &Attribute) -> bool {
     attr.path().is_ident("test")
 }
 
@@ -656,6 +1834,41 @@ fn path_is(path: &syn::Path, idents: &[&str]) -> bool {
     path.segments.iter().map(|ps| &ps.ident).eq(idents.iter())
 }
 
+/// True if this is a `derive` attribute that might implement `Default`.
+///
+/// This includes derives like `SmartDefault` from other crates, and derives inside
+/// `cfg_attr`, like `#[cfg_attr(feature = "x", derive(Default))]`.
+fn attr_may_derive_default(attr: &Attribute) -> bool {
+    meta_may_derive_default(&attr.meta)
+}
+
+fn meta_may_derive_default(meta: &Meta) -> bool {
+    let Meta::List(list) = meta else {
+        return false;
+    };
+    if list.path.is_ident("cfg_attr") {
+        // The first element is the condition; the rest are attributes that might be
+        // applied. If it can't be parsed, assume it might derive Default.
+        return list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .map_or(true, |metas| {
+                metas.iter().skip(1).any(meta_may_derive_default)
+            });
+    }
+    if !list.path.is_ident("derive") {
+        return false;
+    }
+    let mut found = false;
+    // If the attribute can't be parsed, assume it might derive Default.
+    list.parse_nested_meta(|meta| {
+        if let Some(last) = meta.path.segments.last() {
+            found |= last.ident.to_string().contains("Default");
+        }
+        Ok(())
+    })
+    .map_or(true, |()| found)
+}
+
 /// True if the attribute contains `mutants::skip`.
 ///
 /// This for example returns true for `#[mutants::skip] or `#[cfg_attr(test, mutants::skip)]`.
@@ -720,32 +1933,779 @@ mod test {
     use super::*;
     use crate::package::Package;
 
-    /// We should not generate mutants that produce the same tokens as the
-    /// source.
-    #[test]
-    fn no_mutants_equivalent_to_source() {
-        let code = indoc! { "
-            fn always_true() -> bool { true }
-        "};
-        let source_file = SourceFile {
+    /// Return all mutants generated from some code in `src/lib.rs`,
+    /// with some additional genres turned on.
+    fn mutants_in_code(code: &str, genres: &[Genre]) -> Vec<Mutant> {
+        let options = Options {
+            genres: genres.to_vec(),
+            ..Default::default()
+        };
+        mutants_in_code_with_options(code, &options)
+    }
+
+    /// Return all mutants generated from some code in `src/lib.rs`, with given options.
+    ///
+    /// Like [walk_tree], this first finds the types defined in the code.
+    fn mutants_in_code_with_options(code: &str, options: &Options) -> Vec<Mutant> {
+        walk_code(code, options).mutants
+    }
+
+    fn walk_code(code: &str, options: &Options) -> FileMutants {
+        let source_file = source_file_from_code(code);
+        let syn_file = syn::parse_str::<File>(code).expect("parse code");
+        let context = ReplacementContext {
+            local_types: Arc::new(find_definitions(&source_file, &syn_file).local_types),
+            imports: Imports::in_file(&syn_file),
+            ..Default::default()
+        };
+        walk_file(&source_file, &syn_file, &context, options)
+    }
+
+    fn source_file_from_code(code: &str) -> SourceFile {
+        SourceFile {
             code: Arc::new(code.to_owned()),
             package: Arc::new(Package {
                 name: "unimportant".to_owned(),
                 relative_manifest_path: "Cargo.toml".into(),
+                path_dependency_dirs: Vec::new(),
             }),
             tree_relative_path: Utf8PathBuf::from("src/lib.rs"),
             is_top: true,
-        };
-        let (mutants, _files) = walk_file(&source_file, &[]).expect("walk_file");
-        let mutant_names = mutants.iter().map(|m| m.name(false, false)).collect_vec();
+        }
+    }
+
+    /// Return the names of all mutants generated from some code in `src/lib.rs`.
+    fn mutant_names_in_code(code: &str, genres: &[Genre]) -> Vec<String> {
+        mutants_in_code(code, genres)
+            .iter()
+            .map(|m| m.name(false, false))
+            .collect_vec()
+    }
+
+    /// We should not generate mutants that produce the same tokens as the
+    /// source.
+    #[test]
+    fn no_mutants_equivalent_to_source() {
+        let code = indoc! { "
+            fn always_true() -> bool { true }
+        "};
         // It would be good to suggest replacing this with 'false', breaking a key behavior,
         // but bad to replace it with 'true', changing nothing.
         assert_eq!(
-            mutant_names,
+            mutant_names_in_code(code, &[]),
             ["src/lib.rs: replace always_true -> bool with false"]
         );
     }
 
+    #[test]
+    fn condition_mutants_are_off_by_default() {
+        let code = indoc! { "
+            fn f(a: bool) {
+                if a {
+                    println!(\"a\");
+                }
+            }
+        "};
+        assert_eq!(
+            mutant_names_in_code(code, &[]),
+            ["src/lib.rs: replace f with ()"]
+        );
+    }
+
+    #[test]
+    fn replace_if_while_and_guard_conditions() {
+        let code = indoc! { "
+            fn f(x: Option<usize>) {
+                if is_ready() {
+                    start();
+                }
+                while true {
+                    step();
+                }
+                if let Some(a) = x {
+                    start();
+                }
+                match x {
+                    Some(a) if a > 1 => start(),
+                    _ => (),
+                }
+            }
+        "};
+        assert_eq!(
+            mutant_names_in_code(code, &[Genre::Condition]),
+            [
+                "src/lib.rs: replace f with ()",
+                "src/lib.rs: replace condition is_ready() with true in f",
+                "src/lib.rs: replace condition is_ready() with false in f",
+                "src/lib.rs: replace condition true with false in f",
+                "src/lib.rs: replace condition a > 1 with true in f",
+                "src/lib.rs: replace condition a > 1 with false in f",
+                "src/lib.rs: replace > with == in f",
+                "src/lib.rs: replace > with < in f",
+                "src/lib.rs: replace > with >= in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_operands_of_logical_operators() {
+        let code = indoc! { "
+            fn f(a: bool, b: bool, c: bool) -> bool {
+                a && (b || c) && true
+            }
+        "};
+        let names = mutant_names_in_code(code, &[Genre::Condition])
+            .into_iter()
+            .filter(|name| name.contains("condition"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace condition a && (b || c) with true in f",
+                "src/lib.rs: replace condition true with false in f",
+                "src/lib.rs: replace condition a with true in f",
+                "src/lib.rs: replace condition (b || c) with true in f",
+                "src/lib.rs: replace condition (b || c) with false in f",
+                "src/lib.rs: replace condition b with false in f",
+                "src/lib.rs: replace condition c with false in f",
+                "src/lib.rs: replace condition c with true in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn comparison_boundary_mutants() {
+        let code = indoc! { "
+            fn f(a: usize, b: usize) -> bool {
+                a < b && a >= 1
+            }
+        "};
+        let names = mutant_names_in_code(code, &[])
+            .into_iter()
+            .filter(|name| !name.contains("replace f ->") && !name.contains("&&"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace < with == in f",
+                "src/lib.rs: replace < with > in f",
+                "src/lib.rs: replace < with <= in f",
+                "src/lib.rs: replace a < b with true in f",
+                "src/lib.rs: replace a < b with false in f",
+                "src/lib.rs: replace >= with < in f",
+                "src/lib.rs: replace >= with > in f",
+                "src/lib.rs: replace a >= 1 with true in f",
+                "src/lib.rs: replace a >= 1 with false in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn remove_state_changes() {
+        let code = indoc! { "
+            fn f(&mut self, buf: &mut Vec<u8>) -> Vec<u8> {
+                let a = std::mem::take(&mut self.buf);
+                let b = mem::replace(buf, vec![1]);
+                let d = mem::take(&mut *self.guard);
+                mem::swap(&mut self.a, &mut self.b);
+                drop(self.lock.take());
+                self.items.clear();
+                let c = self.iter().take(2);
+                a
+            }
+        "};
+        let names = mutant_names_in_code(code, &[Genre::StateChange])
+            .into_iter()
+            .filter(|name| !name.contains("replace f ->"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace std::mem::take(&mut self.buf) with self.buf.clone() in f",
+                "src/lib.rs: replace mem::replace(buf, vec![1]) with (*buf).clone() in f",
+                "src/lib.rs: replace mem::take(&mut *self.guard) with (*self.guard).clone() in f",
+                "src/lib.rs: replace mem::swap(&mut self.a, &mut self.b) with () in f",
+                "src/lib.rs: replace drop(self.lock.take()) with () in f",
+                "src/lib.rs: replace self.lock.take() with self.lock.clone() in f",
+                "src/lib.rs: replace self.items.clear() with () in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_struct_fields() {
+        let code = indoc! { "
+            fn f(name: String) -> Options {
+                let _unused = Options { a: 1 };
+                Options {
+                    name,
+                    jobs: 4,
+                    verbose: true,
+                    quiet: false,
+                    path: PathBuf::from(\"a\"),
+                    ..Default::default()
+                }
+            }
+            const DEFAULT: Options = Options { jobs: 1 };
+        "};
+        let names = mutant_names_in_code(code, &[Genre::StructField])
+            .into_iter()
+            .filter(|name| !name.contains("replace f ->"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace Options field a with Default::default() in f",
+                "src/lib.rs: replace Options field name with name: Default::default() in f",
+                "src/lib.rs: replace Options field jobs with Default::default() in f",
+                "src/lib.rs: replace Options field verbose with Default::default() in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn collect_local_types() {
+        let code = indoc! { r#"
+            #[derive(Debug, Clone, Copy)]
+            enum Phase { Build, Test, Finished(u32) }
+            #[derive(Default)]
+            enum Value { #[default] Int(i64) }
+            struct NoDefault;
+            #[derive(smart_default::SmartDefault)]
+            struct Smart { a: usize }
+            struct Manual;
+            impl Default for Manual { fn default() -> Manual { Manual } }
+            #[cfg(test)]
+            enum TestOnly { A }
+            #[cfg_attr(feature = "x", derive(Default))]
+            struct SometimesDefault;
+            #[cfg_attr(all(unix, feature = "x"), derive(Debug), derive(Clone))]
+            struct SometimesClone;
+        "#};
+        let local_types = find_definitions(
+            &source_file_from_code(code),
+            &syn::parse_str::<File>(code).unwrap(),
+        )
+        .local_types;
+        assert_eq!(
+            local_types.enums.into_iter().collect_vec(),
+            [(
+                "Phase".to_owned(),
+                vec!["Build".to_owned(), "Test".to_owned()]
+            )]
+        );
+        assert_eq!(
+            local_types.names.iter().sorted().collect_vec(),
+            [
+                "Manual",
+                "NoDefault",
+                "Phase",
+                "Smart",
+                "SometimesClone",
+                "SometimesDefault",
+                "Value"
+            ]
+        );
+        assert_eq!(
+            local_types.with_default.iter().sorted().collect_vec(),
+            ["Manual", "Smart", "SometimesDefault", "Value"]
+        );
+    }
+
+    #[test]
+    fn replace_self_with_other_constructors() {
+        let code = indoc! { "
+            struct S { a: usize }
+            impl S {
+                fn new() -> S { S { a: 0 } }
+                fn empty() -> Self { S { a: 1 } }
+                fn with_a(a: usize) -> Self { S { a } }
+                fn nested(&self) -> usize {
+                    fn inner() -> S { S { a: 2 } }
+                    inner().a
+                }
+            }
+            #[derive(Default)]
+            struct D;
+            impl D {
+                fn make() -> Self { D }
+            }
+            enum E { A, B }
+            impl E {
+                fn first() -> Self { E::A }
+            }
+        "};
+        let names = mutant_names_in_code(code, &[])
+            .into_iter()
+            .filter(|name| name.contains(" -> "))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace S::empty -> Self with Self::new()",
+                "src/lib.rs: replace S::with_a -> Self with Self::new()",
+                "src/lib.rs: replace S::with_a -> Self with Self::empty()",
+                "src/lib.rs: replace S::nested -> usize with 0",
+                "src/lib.rs: replace S::nested -> usize with 1",
+                "src/lib.rs: replace D::make -> Self with Default::default()",
+                "src/lib.rs: replace E::first -> Self with E::B",
+            ]
+        );
+    }
+
+    #[test]
+    fn constructors_that_might_not_be_callable_are_not_used() {
+        let code = indoc! { r#"
+            struct S { a: usize }
+            impl S {
+                fn new() -> S { S { a: 0 } }
+                #[cfg(test)]
+                fn for_test() -> Self { S { a: 1 } }
+                #[cfg(feature = "extra")]
+                fn extra() -> Self { S { a: 2 } }
+                unsafe fn unchecked() -> Self { S { a: 3 } }
+            }
+        "#};
+        let names = mutant_names_in_code(code, &[])
+            .into_iter()
+            .filter(|name| name.contains(" -> "))
+            .collect_vec();
+        assert_eq!(
+            names,
+            ["src/lib.rs: replace S::extra -> Self with Self::new()"]
+        );
+    }
+
+    #[test]
+    fn async_fn_mutants() {
+        let code = indoc! { r#"
+            async fn count(n: usize) -> Result<usize> {
+                let a = fetch(n).await?;
+                Ok(a.len())
+            }
+            struct S;
+            #[async_trait]
+            impl Service for S {
+                async fn name(&self) -> String {
+                    self.fetch().await
+                }
+            }
+            fn spawn(n: usize) -> impl Future<Output = usize> {
+                async move { n + 1 }
+            }
+            fn boxed(&self) -> Pin<Box<dyn Future<Output = bool> + Send + '_>> {
+                Box::pin(async move { self.check()?.await })
+            }
+        "# };
+        let names = mutant_names_in_code(code, &[Genre::FnValue, Genre::ErrorHandling]);
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace count -> Result<usize> with Ok(0)",
+                "src/lib.rs: replace count -> Result<usize> with Ok(1)",
+                "src/lib.rs: replace fetch(n).await? with (match fetch(n).await {Ok(v) => v, Err(_) => return Ok(0)}) in count",
+                "src/lib.rs: replace <impl Service for S>::name -> String with String::new()",
+                r#"src/lib.rs: replace <impl Service for S>::name -> String with "xyzzy".into()"#,
+                "src/lib.rs: replace spawn -> impl Future<Output = usize> with async {0}",
+                "src/lib.rs: replace spawn -> impl Future<Output = usize> with async {1}",
+                "src/lib.rs: replace + with - in spawn",
+                "src/lib.rs: replace + with * in spawn",
+                "src/lib.rs: replace boxed -> Pin<Box<dyn Future<Output = bool> + Send + '_>> with Box::pin(async {true})",
+                "src/lib.rs: replace boxed -> Pin<Box<dyn Future<Output = bool> + Send + '_>> with Box::pin(async {false})",
+            ]
+        );
+    }
+
+    #[test]
+    fn generic_return_types_use_bounds() {
+        let code = indoc! { "
+            fn parse<T: FromStr>(s: &str) -> T { s.parse().unwrap() }
+            fn make<T>() -> T where T: Default { T::default() }
+            fn wrap<T: From<bool>>(b: bool) -> Option<T> { Some(b.into()) }
+            struct W<T>(T);
+            impl<T: Default> W<T> {
+                fn get(&self) -> T { T::default() }
+                fn other<U: Zero>(&self) -> U { U::zero() }
+            }
+        "};
+        let names = mutant_names_in_code(code, &[]);
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace make -> T with Default::default()",
+                "src/lib.rs: replace wrap -> Option<T> with None",
+                "src/lib.rs: replace wrap -> Option<T> with Some(T::from(true))",
+                "src/lib.rs: replace wrap -> Option<T> with Some(T::from(false))",
+                "src/lib.rs: replace W<T>::get -> T with Default::default()",
+            ]
+        );
+        assert_eq!(
+            walk_code(code, &Options::default()).skipped_generic_fns,
+            ["src/lib.rs:1: parse -> T"]
+        );
+    }
+
+    #[test]
+    fn replace_constant_values() {
+        let code = indoc! { r#"
+            const MAX_SIZE: u32 = 1024;
+            static GREETING: &str = "hello";
+            const _: () = assert!(true);
+            mod limits {
+                use std::time::Duration;
+                pub const TIMEOUT: Duration = Duration::from_secs(60);
+            }
+            struct S;
+            impl S {
+                const ZERO: i64 = 0;
+            }
+            #[mutants::skip]
+            const SKIPPED: u8 = 1;
+            const MAYBE: Option<u8> = None;
+            static LABEL: String = String::new();
+            const NAMES: &[&str] = &["a"];
+            const CONFIG: Option<Config> = None;
+        "# };
+        let mutants = mutants_in_code(code, &[Genre::Constant]);
+        assert!(mutants.iter().all(|m| m.function.is_none()));
+        assert_eq!(
+            mutants.iter().map(|m| m.name(false, false)).collect_vec(),
+            [
+                "src/lib.rs: replace const MAX_SIZE with 0",
+                "src/lib.rs: replace const MAX_SIZE with 1",
+                "src/lib.rs: replace const MAX_SIZE with 1025",
+                "src/lib.rs: replace const MAX_SIZE with 1023",
+                r#"src/lib.rs: replace static GREETING with """#,
+                r#"src/lib.rs: replace static GREETING with "xyzzy""#,
+                "src/lib.rs: replace const limits::TIMEOUT with Duration::ZERO",
+                "src/lib.rs: replace const limits::TIMEOUT with Duration::from_secs(1)",
+                "src/lib.rs: replace const limits::TIMEOUT with Duration::MAX",
+                "src/lib.rs: replace const S::ZERO with 1",
+                "src/lib.rs: replace const S::ZERO with -1",
+                "src/lib.rs: replace const MAYBE with Some(0)",
+                "src/lib.rs: replace const MAYBE with Some(1)",
+            ]
+        );
+        // Not generated by default.
+        assert_eq!(mutants_in_code(code, &[]), []);
+    }
+
+    #[test]
+    fn constant_literals_stay_in_the_range_of_the_declared_type() {
+        let code = indoc! { "
+            const X: u8 = 255;
+            const Y: u32 = 0;
+            static Z: i8 = 127;
+        " };
+        assert_eq!(
+            mutant_names_in_code(code, &[Genre::Constant]),
+            [
+                "src/lib.rs: replace const X with 0",
+                "src/lib.rs: replace const X with 1",
+                "src/lib.rs: replace const X with 254",
+                "src/lib.rs: replace const Y with 1",
+                "src/lib.rs: replace static Z with 0",
+                "src/lib.rs: replace static Z with 1",
+                "src/lib.rs: replace static Z with -1",
+                "src/lib.rs: replace static Z with 126",
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_call_statements() {
+        let code = indoc! { "
+            fn f(v: &mut Vec<usize>) -> usize {
+                let a = g(1);
+                v.push(a);
+                self::g(2);
+                v.len();
+                for x in v.iter() {
+                    println!(\"{x}\");
+                    h(x);
+                    h(x + 1);
+                }
+                a += 1;
+                exit(1);
+            }
+        "};
+        let names = mutant_names_in_code(code, &[Genre::Statement])
+            .into_iter()
+            .filter(|name| name.contains("statement"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: delete statement v.push(a) in f",
+                "src/lib.rs: delete statement self::g(2) in f",
+                "src/lib.rs: delete statement v.len() in f",
+                "src/lib.rs: delete statement h(x) in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_literals() {
+        let code = indoc! { r#"
+            #[doc = "not this"]
+            fn f(x: usize) -> [u8; 4] {
+                let buf = [0u8; 64];
+                let retries = 3;
+                let full = 255u8;
+                let big = 2147483647i32;
+                let ok = true;
+                let name = "hello";
+                let c = 'c';
+                match x {
+                    1 => (),
+                    _ => g::<5>(0.5, 1.5f32),
+                }
+                buf
+            }
+        "# };
+        let names = mutant_names_in_code(code, &[Genre::Literal])
+            .into_iter()
+            .filter(|name| !name.contains("replace f ->"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace 0u8 with 1u8 in f",
+                "src/lib.rs: replace 3 with 0 in f",
+                "src/lib.rs: replace 3 with 4 in f",
+                "src/lib.rs: replace 3 with 2 in f",
+                "src/lib.rs: replace 255u8 with 0u8 in f",
+                "src/lib.rs: replace 255u8 with 254u8 in f",
+                "src/lib.rs: replace 2147483647i32 with 0i32 in f",
+                "src/lib.rs: replace 2147483647i32 with 2147483646i32 in f",
+                "src/lib.rs: replace true with false in f",
+                r#"src/lib.rs: replace "hello" with "" in f"#,
+                r"src/lib.rs: replace 'c' with '\0' in f",
+                "src/lib.rs: replace 0.5 with 0.0 in f",
+                "src/lib.rs: replace 0.5 with 1.5 in f",
+                "src/lib.rs: replace 0.5 with (-0.5) in f",
+                "src/lib.rs: replace 1.5f32 with 0.0f32 in f",
+                "src/lib.rs: replace 1.5f32 with 2.5f32 in f",
+                "src/lib.rs: replace 1.5f32 with 0.5f32 in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn literal_mutants_are_not_generated_outside_functions() {
+        let code = indoc! { "
+            const N: usize = 10;
+        "};
+        assert_eq!(
+            mutant_names_in_code(code, &[Genre::Literal]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn range_mutants() {
+        let code = indoc! { "
+            fn f(v: &[usize], n: usize) -> usize {
+                for i in 0..n {
+                    g(i);
+                }
+                let a = &v[1..=self.end];
+                let b = &v[..v.len()];
+                let c = &v[1..];
+                let d = &v[..f(n) * 2];
+                0
+            }
+        "};
+        let names = mutant_names_in_code(code, &[Genre::Range])
+            .into_iter()
+            .filter(|name| !name.contains("replace f ->") && !name.contains('*'))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace .. with ..= in f",
+                "src/lib.rs: replace n with n - 1 in f",
+                "src/lib.rs: replace ..= with .. in f",
+                "src/lib.rs: replace self.end with self.end + 1 in f",
+                "src/lib.rs: replace .. with ..= in f",
+                "src/lib.rs: replace v.len() with v.len() - 1 in f",
+                "src/lib.rs: replace .. with ..= in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_errors_and_unwrap_defaults() {
+        let code = indoc! { "
+            fn f(p: &Path) -> Result<usize> {
+                let s = read(p)?;
+                let n = s.parse()?.abs();
+                let g = || -> Result<()> { h()?; Ok(()) };
+                f(p)? + 1;
+                Ok(n.unwrap_or(2) + s.len().checked_sub(1).unwrap_or_default())
+            }
+            fn g(v: &[usize]) -> Option<bool> {
+                Some(*v.first()? > 0)
+            }
+        "};
+        let names = mutant_names_in_code(code, &[Genre::ErrorHandling])
+            .into_iter()
+            .filter(|name| !name.contains(" -> "))
+            .filter(|name| !name.contains(" + ") && !name.contains(" > "))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace read(p)? with (match read(p) {Ok(v) => v, Err(_) => return Ok(0)}) in f",
+                "src/lib.rs: replace s.parse()? with (match s.parse() {Ok(v) => v, Err(_) => return Ok(0)}) in f",
+                "src/lib.rs: replace h()? with (match h() {Ok(v) => v, Err(_) => return Ok(())}) in f::{closure}",
+                "src/lib.rs: replace f(p)? with (match f(p) {Ok(v) => v, Err(_) => return Ok(0)}) in f",
+                "src/lib.rs: replace unwrap_or(2) with unwrap_or_default() in f",
+                "src/lib.rs: replace unwrap_or_default() with unwrap() in f",
+                "src/lib.rs: replace v.first()? with (match v.first() {Some(v) => v, None => return Some(true)}) in g",
+            ]
+        );
+    }
+
+    #[test]
+    fn swap_methods() {
+        let code = indoc! { "
+            fn f(v: &[usize], s: &str, o: &mut Option<usize>) -> bool {
+                v.iter().take(2).any(|x| x.min(3) > 1) && s.starts_with('a') && v.first().is_some()
+                    || o.take().is_none()
+            }
+        "};
+        let options = Options {
+            genres: vec![Genre::MethodSwap],
+            method_swaps: vec![("iter".to_owned(), "into_iter".to_owned())],
+            ..Default::default()
+        };
+        let names = mutants_in_code_with_options(code, &options)
+            .into_iter()
+            .filter(|m| m.genre == Genre::MethodSwap)
+            .map(|m| m.name(false, false))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace any with all in f",
+                "src/lib.rs: replace take with skip in f",
+                "src/lib.rs: replace iter with into_iter in f",
+                "src/lib.rs: replace min with max in f::{closure}",
+                "src/lib.rs: replace starts_with with ends_with in f",
+                "src/lib.rs: replace is_some with is_none in f",
+                "src/lib.rs: replace first with last in f",
+                "src/lib.rs: replace is_none with is_some in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_closure_bodies() {
+        let code = indoc! { "
+            fn f(v: &mut Vec<usize>) {
+                v.retain(|x| *x > 2);
+                let c = |a: usize| -> usize { a * 2 };
+                v.sort_by(|a, b| a.cmp(b));
+                let d = v.iter().filter(|x| true).count();
+            }
+        "};
+        let names = mutant_names_in_code(code, &[])
+            .into_iter()
+            .filter(|name| name.contains("{closure}"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: replace f::{closure} -> bool with true",
+                "src/lib.rs: replace f::{closure} -> bool with false",
+                "src/lib.rs: replace > with == in f::{closure}",
+                "src/lib.rs: replace > with < in f::{closure}",
+                "src/lib.rs: replace > with >= in f::{closure}",
+                "src/lib.rs: replace f::{closure} -> usize with 0",
+                "src/lib.rs: replace f::{closure} -> usize with 1",
+                "src/lib.rs: replace * with + in f::{closure}",
+                "src/lib.rs: replace * with / in f::{closure}",
+                "src/lib.rs: replace f::{closure} -> bool with false",
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_match_arms_only_with_catchall() {
+        let code = indoc! { "
+            fn f(x: Option<usize>) -> usize {
+                match x {
+                    Some(0) => 1,
+                    Some(a) if a > 10 => { a }
+                    _ => 2,
+                }
+            }
+
+            fn g(x: Option<usize>) -> usize {
+                match x {
+                    Some(a) => a,
+                    None => 0,
+                }
+            }
+        "};
+        let names = mutant_names_in_code(code, &[Genre::Branch])
+            .into_iter()
+            .filter(|name| name.contains("delete"))
+            .collect_vec();
+        assert_eq!(
+            names,
+            [
+                "src/lib.rs: delete match arm Some(0) in f",
+                "src/lib.rs: delete match arm Some(a) in f",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_else_blocks_in_statements() {
+        let code = indoc! { "
+            fn f(a: bool, b: bool) {
+                if a {
+                    start();
+                } else if b {
+                    stop();
+                } else {
+                    wait();
+                }
+                let x = if a { 1 } else { 2 };
+                if b {
+                    start();
+                } else {
+                    stop();
+                }
+            }
+
+            fn g(a: bool) -> usize {
+                if a { 1 } else { 2 }
+            }
+        "};
+        let mutants = mutants_in_code(code, &[Genre::Branch]);
+        let else_mutants = mutants
+            .iter()
+            .filter(|m| m.genre == Genre::Branch)
+            .collect_vec();
+        assert_eq!(
+            else_mutants
+                .iter()
+                .map(|m| m.name(true, false))
+                .collect_vec(),
+            [
+                "src/lib.rs:12:12: replace else block with {} in f",
+                "src/lib.rs:6:12: replace else block with {} in f",
+            ]
+        );
+        assert_eq!(
+            else_mutants[0].mutated_code().lines().nth(11).unwrap(),
+            "    } else {} /* ~ changed by cargo-mutants ~ */"
+        );
+    }
+
     /// We don't visit functions inside files marked with `#![cfg(test)]`.
     #[test]
     fn no_mutants_in_files_with_inner_cfg_test_attribute() {
@@ -758,6 +2718,56 @@ mod test {
         assert_eq!(discovered.mutants.as_slice(), &[]);
     }
 
+    /// Types are only looked up in the package of the file being mutated, so a type
+    /// of the same name without `Default` in another package doesn't matter.
+    #[test]
+    fn local_types_are_only_used_within_their_package() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tree = Utf8Path::from_path(tmp.path()).unwrap();
+        let lib_code = indoc! {"
+            #[derive(Default)]
+            pub struct Config {}
+
+            pub fn config() -> Config {
+                Config {}
+            }
+        "};
+        let other_code = indoc! {"
+            pub struct Config(u32);
+
+            pub fn config() -> Config {
+                Config(1)
+            }
+        "};
+        std::fs::create_dir_all(tree.join("lib/src")).unwrap();
+        std::fs::create_dir_all(tree.join("other/src")).unwrap();
+        std::fs::write(tree.join("lib/src/lib.rs"), lib_code).unwrap();
+        std::fs::write(tree.join("other/src/lib.rs"), other_code).unwrap();
+        let source_files = ["lib", "other"]
+            .into_iter()
+            .map(|name| {
+                let package = Arc::new(Package {
+                    name: name.to_owned(),
+                    relative_manifest_path: format!("{name}/Cargo.toml").into(),
+                    path_dependency_dirs: Vec::new(),
+                });
+                SourceFile::new(tree, format!("{name}/src/lib.rs").into(), &package, true)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect_vec();
+        let discovered =
+            walk_tree(tree, &source_files, &Options::default(), &Console::new()).unwrap();
+        assert_eq!(
+            discovered
+                .mutants
+                .iter()
+                .map(|m| m.name(true, false))
+                .collect_vec(),
+            ["lib/src/lib.rs:5:5: replace config -> Config with Default::default()"]
+        );
+    }
+
     /// Helper function for `find_path_attribute` tests
     fn run_find_path_attribute(
         token_stream: TokenStream,
@@ -1,5 +1,6 @@
 // Copyright 2023 Martin Pool
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::panic::catch_unwind;
 use std::sync::Arc;
@@ -165,6 +166,7 @@ impl Workspace {
             let package = Arc::new(Package {
                 name: package_metadata.name.clone(),
                 relative_manifest_path,
+                path_dependency_dirs: path_dependency_dirs(&self.metadata, package_metadata),
             });
             tops.push(PackageTop {
                 package,
@@ -231,6 +233,43 @@ impl Workspace {
 /// Find all the files that are named in the `path` of targets in a Cargo manifest that should be tested.
 ///
 /// These are the starting points for discovering source files.
+/// Find the directories of the packages that a package depends on by path, including
+/// the dependencies of those that are members of the workspace.
+///
+/// The path dependencies of packages outside the workspace aren't known, because the
+/// metadata only describes the members.
+fn path_dependency_dirs(
+    metadata: &cargo_metadata::Metadata,
+    package_metadata: &cargo_metadata::Package,
+) -> Vec<Utf8PathBuf> {
+    let package_dir = |package: &cargo_metadata::Package| {
+        package
+            .manifest_path
+            .parent()
+            .expect("manifest has a parent directory")
+            .to_owned()
+    };
+    let mut found = BTreeSet::new();
+    let mut queue = vec![package_metadata];
+    while let Some(package) = queue.pop() {
+        for dir in package
+            .dependencies
+            .iter()
+            .filter_map(|dep| dep.path.as_ref())
+        {
+            if *dir != package_dir(package_metadata) && found.insert(dir.clone()) {
+                queue.extend(
+                    metadata
+                        .workspace_packages()
+                        .into_iter()
+                        .filter(|member| package_dir(member) == *dir),
+                );
+            }
+        }
+    }
+    found.into_iter().collect()
+}
+
 fn direct_package_sources(
     workspace_root: &Utf8Path,
     package_metadata: &cargo_metadata::Package,
@@ -382,6 +421,29 @@ mod test {
         assert_eq!(packages.iter().map(|p| &p.name).collect_vec(), ["main"]);
     }
 
+    #[test]
+    fn packages_know_their_path_dependencies() {
+        let workspace = Workspace::open("testdata/workspace").unwrap();
+        let packages = workspace.packages(&PackageFilter::All).unwrap();
+        assert_eq!(
+            packages
+                .iter()
+                .map(|p| (
+                    p.name.as_str(),
+                    p.path_dependency_dirs
+                        .iter()
+                        .map(|dir| dir.strip_prefix(&workspace.dir).unwrap().as_str())
+                        .collect_vec()
+                ))
+                .collect_vec(),
+            [
+                ("cargo_mutants_testdata_workspace_utils", vec![]),
+                ("main", vec!["utils"]),
+                ("main2", vec!["utils"]),
+            ]
+        );
+    }
+
     #[test]
     fn auto_packages_in_virtual_workspace_gets_everything() {
         let path = Utf8Path::new("testdata/workspace");
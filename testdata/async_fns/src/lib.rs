@@ -0,0 +1,95 @@
+//! Async functions, and functions returning futures, whose mutants should
+//! all build and be caught by the tests.
+//!
+//! This tree has no dependencies, so the tests run the futures with a
+//! minimal executor, and the boxed futures are written out by hand in the
+//! style generated by `async-trait`.
+
+use std::future::{ready, Future};
+use std::num::ParseIntError;
+use std::pin::Pin;
+
+pub async fn double(x: u32) -> u32 {
+    ready(x).await * 2
+}
+
+async fn parse(s: &str) -> Result<u32, ParseIntError> {
+    s.parse()
+}
+
+pub async fn parse_sum(a: &str, b: &str) -> Result<u32, ParseIntError> {
+    let a = parse(a).await?;
+    let b = parse(b).await?;
+    Ok(a + b)
+}
+
+/// An async block returned from a non-async function.
+pub fn delayed_len(s: &str) -> impl Future<Output = usize> + '_ {
+    async move { ready(s).await.len() }
+}
+
+pub trait Greeter {
+    fn greet<'a>(&'a self, name: &'a str) -> Pin<Box<dyn Future<Output = String> + Send + 'a>>;
+}
+
+pub struct English;
+
+impl Greeter for English {
+    fn greet<'a>(&'a self, name: &'a str) -> Pin<Box<dyn Future<Output = String> + Send + 'a>> {
+        Box::pin(async move { format!("Hello, {name}!") })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    use super::*;
+
+    /// Run a future to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct ThreadWaker(Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn double_three() {
+        assert_eq!(block_on(double(3)), 6);
+    }
+
+    #[test]
+    fn parse_and_sum() {
+        assert_eq!(block_on(parse_sum("2", "3")), Ok(5));
+        assert!(block_on(parse_sum("two", "3")).is_err());
+        assert!(block_on(parse_sum("2", "three")).is_err());
+    }
+
+    #[test]
+    fn len_of_string() {
+        assert_eq!(block_on(delayed_len("abc")), 3);
+    }
+
+    #[test]
+    fn greet_in_english() {
+        assert_eq!(block_on(English.greet("world")), "Hello, world!");
+    }
+}
@@ -0,0 +1,32 @@
+//! Example of a struct with no Default that generates unviable mutants.
+//!
+//! The struct is defined by a macro, so cargo-mutants can't see that it
+//! doesn't implement `Default`.
+
+#![allow(dead_code)]
+
+macro_rules! define_s {
+    () => {
+        pub struct S {
+            a: &'static str,
+            b: usize,
+        }
+    };
+}
+
+define_s!();
+
+// This can't be called "new" because that name is specifically excluded.
+pub fn make_an_s() -> S {
+    S {
+        a: "on the beach",
+        b: 99,
+    }
+}
+
+#[test]
+fn test_new_s() {
+    let s = make_an_s();
+    assert!(!s.a.is_empty());
+    assert_eq!(s.b, 99);
+}
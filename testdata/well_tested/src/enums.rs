@@ -0,0 +1,27 @@
+/// An enum defined in this crate, with unit variants.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Phase {
+    Build,
+    Test,
+    Finished(u32),
+}
+
+/// Can be mutated to return each unit variant of `Phase`.
+fn next_phase(phase: Phase) -> Phase {
+    match phase {
+        Phase::Build => Phase::Test,
+        Phase::Test => Phase::Finished(0),
+        Phase::Finished(n) => Phase::Finished(n),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn next_phases() {
+        assert_eq!(next_phase(Phase::Build), Phase::Test);
+        assert_eq!(next_phase(Phase::Test), Phase::Finished(0));
+    }
+}
@@ -11,6 +11,7 @@
 mod arc;
 mod booleans;
 mod empty_fns;
+mod enums;
 mod inside_mod;
 mod item_mod;
 mod methods;
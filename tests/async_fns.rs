@@ -0,0 +1,20 @@
+// Copyright 2024 Martin Pool
+
+//! Tests for mutants of async functions, and functions returning futures.
+
+use predicates::prelude::*;
+
+mod util;
+use util::{copy_of_testdata, run};
+
+#[test]
+fn async_fn_mutants_are_viable_and_caught() {
+    let tmp_src_dir = copy_of_testdata("async_fns");
+    run()
+        .args(["mutants", "--no-times", "--no-shuffle"])
+        .arg("-d")
+        .arg(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("16 mutants tested: 16 caught"));
+}
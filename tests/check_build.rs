@@ -126,14 +126,17 @@ fn check_tree_with_mutants_skip() {
         .success()
         .stdout(predicate::function(|stdout: &str| {
             insta::assert_snapshot!(stdout, @r###"
-            Found 5 mutants to test
+            Found 8 mutants to test
             ok       Unmutated baseline
             ok       src/lib.rs:15:5: replace controlled_loop with ()
             ok       src/lib.rs:21:28: replace > with == in controlled_loop
             ok       src/lib.rs:21:28: replace > with < in controlled_loop
+            ok       src/lib.rs:21:28: replace > with >= in controlled_loop
+            ok       src/lib.rs:21:12: replace start.elapsed() > Duration::from_secs(60 * 5) with true in controlled_loop
+            ok       src/lib.rs:21:12: replace start.elapsed() > Duration::from_secs(60 * 5) with false in controlled_loop
             ok       src/lib.rs:21:53: replace * with + in controlled_loop
             ok       src/lib.rs:21:53: replace * with / in controlled_loop
-            5 mutants tested: 5 succeeded
+            8 mutants tested: 8 succeeded
             "###);
             true
         }));
@@ -142,10 +145,10 @@ fn check_tree_with_mutants_skip() {
         serde_json::json!({
             "caught": 0,
             "missed": 0,
-            "success": 5,
+            "success": 8,
             "timeout": 0,
             "unviable": 0,
-            "total_mutants": 5,
+            "total_mutants": 8,
         })
     );
 }
@@ -176,7 +179,29 @@ fn check_tree_where_build_fails() {
 
 #[test]
 fn unviable_mutation_of_struct_with_no_default() {
+    // The struct is defined in the tree without `Default`, so no unviable
+    // `Default::default()` mutant is generated.
     let tmp_src_dir = copy_of_testdata("struct_with_no_default");
+    run()
+        .args([
+            "mutants",
+            "--line-col=false",
+            "--check",
+            "--no-times",
+            "--no-shuffle",
+            "-v",
+            "-V",
+        ])
+        .arg("-d")
+        .arg(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 0 mutants to test"));
+}
+
+#[test]
+fn unviable_mutation_of_struct_from_macro() {
+    let tmp_src_dir = copy_of_testdata("struct_from_macro");
     run()
         .args([
             "mutants",
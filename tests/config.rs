@@ -55,6 +55,47 @@ fn invalid_field_rejected() {
         );
 }
 
+#[test]
+fn replacements_from_config_file() {
+    let testdata = copy_of_testdata("struct_with_no_default");
+    write_config_file(
+        &testdata,
+        indoc! { r#"
+            [replacements]
+            S = ['S { a: "", b: 0 }', 'S { a: "xyzzy", b: 1 }']
+        "# },
+    );
+    run()
+        .args(["mutants", "--list", "--line-col=false", "-d"])
+        .arg(testdata.path())
+        .assert()
+        .success()
+        .stdout(indoc! { r#"
+            src/lib.rs: replace make_an_s -> S with S {a: "", b: 0}
+            src/lib.rs: replace make_an_s -> S with S {a: "xyzzy", b: 1}
+        "# });
+}
+
+#[test]
+fn invalid_replacement_in_config_file_rejected() {
+    let testdata = copy_of_testdata("struct_with_no_default");
+    write_config_file(
+        &testdata,
+        indoc! { r#"
+            [replacements]
+            S = ["S::new($T)"]
+        "# },
+    );
+    run()
+        .args(["mutants", "--list", "-d"])
+        .arg(testdata.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Replacement for \"S\" uses $T, which is not a generic argument of the pattern",
+        ));
+}
+
 #[test]
 fn list_with_config_file_exclusion() {
     let testdata = copy_of_testdata("well_tested");
@@ -149,6 +190,7 @@ fn exclude_file_argument_overrides_config() {
             src/lib.rs
             src/arc.rs
             src/empty_fns.rs
+            src/enums.rs
             src/methods.rs
             src/result.rs
             src/traits.rs
@@ -0,0 +1,94 @@
+// Copyright 2024 Martin Pool
+
+//! Tests for `--coverage-report`, skipping mutants in code that's never executed.
+
+use std::fs;
+
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+
+mod util;
+use util::{copy_of_testdata, run};
+
+/// Make a copy of `small_well_tested` with an extra untested function, and an LCOV
+/// report from a different directory saying it's not executed.
+fn tree_with_untested_function() -> tempfile::TempDir {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    let lib_path = tmp_src_dir.path().join("src/lib.rs");
+    let mut code = fs::read_to_string(&lib_path).unwrap();
+    code.push_str("\npub fn double(n: u32) -> u32 {\n    n * 2\n}\n");
+    fs::write(&lib_path, code).unwrap();
+    fs::write(
+        tmp_src_dir.path().join("coverage.lcov"),
+        "SF:/home/ci/small_well_tested/src/lib.rs\n\
+        DA:4,2\nDA:5,2\nDA:6,10\nDA:7,10\nDA:8,10\nDA:9,2\nDA:10,2\n\
+        DA:23,0\nDA:24,0\nDA:25,0\n\
+        end_of_record\n",
+    )
+    .unwrap();
+    tmp_src_dir
+}
+
+#[test]
+fn uncovered_mutants_are_listed_and_skipped() {
+    let tmp_src_dir = tree_with_untested_function();
+    run()
+        .args([
+            "mutants",
+            "--coverage-report=coverage.lcov",
+            "--no-shuffle",
+            "--no-times",
+        ])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Skipping 4 mutants in code not covered by coverage.lcov; see uncovered.txt",
+        ))
+        .stdout(predicate::str::contains("4 mutants tested: 4 caught\n"));
+    assert_eq!(
+        fs::read_to_string(tmp_src_dir.path().join("mutants.out/uncovered.txt")).unwrap(),
+        "src/lib.rs:24:5: replace double -> u32 with 0\n\
+        src/lib.rs:24:5: replace double -> u32 with 1\n\
+        src/lib.rs:24:7: replace * with + in double\n\
+        src/lib.rs:24:7: replace * with / in double\n"
+    );
+}
+
+#[test]
+fn nothing_is_built_if_all_mutants_are_uncovered() {
+    let tmp_src_dir = tree_with_untested_function();
+    run()
+        .args([
+            "mutants",
+            "--coverage-report=coverage.lcov",
+            "--file=src/lib.rs",
+            "--re=double",
+            "--no-times",
+        ])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Skipping 4 mutants"))
+        .stdout(predicate::str::contains("Unmutated baseline").not());
+    assert_eq!(
+        fs::read_to_string(tmp_src_dir.path().join("mutants.out/uncovered.txt"))
+            .unwrap()
+            .lines()
+            .count(),
+        4
+    );
+}
+
+#[test]
+fn missing_coverage_report_is_an_error() {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    run()
+        .args(["mutants", "--coverage-report=nonexistent.lcov"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "read coverage report \"nonexistent.lcov\"",
+        ));
+}
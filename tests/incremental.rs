@@ -0,0 +1,101 @@
+// Copyright 2024 Martin Pool
+
+//! Tests for `--incremental`, reusing outcomes from a previous run.
+
+use std::fs;
+
+use predicates::prelude::*;
+
+mod util;
+use util::{copy_of_testdata, outcome_json, run};
+
+fn reused_count(tmp_src_dir: &tempfile::TempDir) -> usize {
+    outcome_json(tmp_src_dir)["outcomes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|outcome| outcome["reused"] == true)
+        .count()
+}
+
+#[test]
+fn unchanged_mutants_are_reused() {
+    let tmp_src_dir = copy_of_testdata("integration_tests");
+    run()
+        .args(["mutants", "--incremental", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("No previous output to reuse"))
+        .stdout(predicate::str::contains("4 mutants tested: 4 caught\n"));
+    assert_eq!(reused_count(&tmp_src_dir), 0);
+
+    run()
+        .args(["mutants", "--incremental", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "4 mutants tested: 4 caught (4 reused from the previous run)",
+        ));
+    assert_eq!(reused_count(&tmp_src_dir), 4);
+    assert_eq!(
+        fs::read_to_string(tmp_src_dir.path().join("mutants.out/caught.txt"))
+            .unwrap()
+            .lines()
+            .count(),
+        4
+    );
+
+    // Without --incremental, everything is tested again.
+    run()
+        .args(["mutants", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("4 mutants tested: 4 caught\n"));
+    assert_eq!(reused_count(&tmp_src_dir), 0);
+}
+
+#[test]
+fn changed_tests_are_run_again() {
+    let tmp_src_dir = copy_of_testdata("integration_tests");
+    run()
+        .args(["mutants", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success();
+    let test_path = tmp_src_dir.path().join("tests/api.rs");
+    fs::write(test_path, "#[test]\nfn nothing() {}\n").unwrap();
+    run()
+        .args(["mutants", "--incremental", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("4 mutants tested: 4 missed\n"));
+    assert_eq!(reused_count(&tmp_src_dir), 0);
+}
+
+#[test]
+fn changed_unit_tests_are_run_again() {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    run()
+        .args(["mutants", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success();
+    let lib_path = tmp_src_dir.path().join("src/lib.rs");
+    let lib = fs::read_to_string(&lib_path).unwrap();
+    fs::write(
+        &lib_path,
+        lib.replace("assert_eq!(factorial(6), 720);", "factorial(6);"),
+    )
+    .unwrap();
+    run()
+        .args(["mutants", "--incremental", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("4 mutants tested: 4 missed\n"));
+    assert_eq!(reused_count(&tmp_src_dir), 0);
+}
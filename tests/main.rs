@@ -248,7 +248,32 @@ fn well_tested_tree_check_only_shuffled() {
 
 #[test]
 fn unviable_mutation_of_struct_with_no_default() {
+    // The struct is defined in the tree without `Default`, so no unviable
+    // `Default::default()` mutant is generated.
     let tmp_src_dir = copy_of_testdata("struct_with_no_default");
+    run()
+        .args([
+            "mutants",
+            "--line-col=false",
+            "--no-times",
+            "--no-shuffle",
+            "-v",
+            "-V",
+        ])
+        .arg("-d")
+        .arg(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(contains("unviable").not());
+    check_text_list_output(
+        tmp_src_dir.path(),
+        "unviable_mutation_of_struct_with_no_default",
+    );
+}
+
+#[test]
+fn unviable_mutation_of_struct_from_macro() {
+    let tmp_src_dir = copy_of_testdata("struct_from_macro");
     run()
         .args([
             "mutants",
@@ -268,10 +293,7 @@ fn unviable_mutation_of_struct_with_no_default() {
             )
             .unwrap(),
         );
-    check_text_list_output(
-        tmp_src_dir.path(),
-        "unviable_mutation_of_struct_with_no_default",
-    );
+    check_text_list_output(tmp_src_dir.path(), "unviable_mutation_of_struct_from_macro");
 }
 
 #[test]
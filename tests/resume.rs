@@ -0,0 +1,104 @@
+// Copyright 2024 Martin Pool
+
+//! Tests for `--resume`, continuing an interrupted run.
+
+use std::fs;
+
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+
+mod util;
+use util::{copy_of_testdata, outcome_json, outcome_json_counts, run};
+
+/// Pretend the run was interrupted after the first `n` outcomes were written.
+fn truncate_outcomes(tmp_src_dir: &tempfile::TempDir, n: usize) {
+    let mut json = outcome_json(tmp_src_dir);
+    json["outcomes"].as_array_mut().unwrap().truncate(n);
+    fs::write(
+        tmp_src_dir.path().join("mutants.out/outcomes.json"),
+        serde_json::to_string_pretty(&json).unwrap(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn resume_tests_only_mutants_without_outcomes() {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    run()
+        .args(["mutants", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success();
+    // The baseline and one mutant.
+    truncate_outcomes(&tmp_src_dir, 2);
+    run()
+        .args(["mutants", "--resume", "--no-times", "--caught"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Resuming: 1 of 4 mutants were already tested",
+        ))
+        .stdout(predicate::str::contains("4 mutants tested: 4 caught\n"))
+        .stdout(predicate::function(|stdout: &str| {
+            stdout.matches("caught   ").count() == 3
+        }));
+    assert_eq!(
+        outcome_json_counts(&tmp_src_dir),
+        serde_json::json!({
+            "success": 0,
+            "caught": 4,
+            "unviable": 0,
+            "missed": 0,
+            "timeout": 0,
+            "total_mutants": 4,
+        })
+    );
+    // The output directory was continued, not rotated.
+    assert!(!tmp_src_dir.path().join("mutants.out.old").exists());
+
+    // If everything was already tested, nothing is built.
+    run()
+        .args(["mutants", "--resume", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Resuming: 4 of 4 mutants were already tested",
+        ))
+        .stdout(predicate::str::contains("Unmutated baseline").not())
+        .stdout(predicate::str::contains("4 mutants tested: 4 caught\n"));
+}
+
+#[test]
+fn resume_fails_if_mutants_changed() {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    run()
+        .args(["mutants", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success();
+    let lib_path = tmp_src_dir.path().join("src/lib.rs");
+    let code = fs::read_to_string(&lib_path).unwrap();
+    fs::write(lib_path, format!("\n{code}")).unwrap();
+    run()
+        .args(["mutants", "--resume", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "don't match the source tree; run again without --resume",
+        ));
+}
+
+#[test]
+fn resume_without_previous_output_tests_everything() {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    run()
+        .args(["mutants", "--resume", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("No previous output to resume"))
+        .stdout(predicate::str::contains("4 mutants tested: 4 caught\n"));
+}
@@ -0,0 +1,146 @@
+// Copyright 2024 Martin Pool
+
+//! Tests for `--schemata`, building mutants together.
+
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+
+mod util;
+use util::{copy_of_testdata, outcome_json_counts, run};
+
+#[test]
+fn small_well_tested_mutants_built_together() {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    run()
+        .args([
+            "mutants",
+            "--schemata",
+            "--no-shuffle",
+            "--no-times",
+            "--caught",
+        ])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::function(|stdout: &str| {
+            insta::assert_snapshot!(stdout, @r###"
+            Found 4 mutants to test
+            ok       Unmutated baseline
+            caught   src/lib.rs:5:5: replace factorial -> u32 with 0
+            caught   src/lib.rs:5:5: replace factorial -> u32 with 1
+            caught   src/lib.rs:7:11: replace *= with += in factorial
+            caught   src/lib.rs:7:11: replace *= with /= in factorial
+            4 mutants tested: 4 caught
+            "###);
+            true
+        }));
+    let log_dir = tmp_src_dir.path().join("mutants.out/log");
+    let schemata_log = std::fs::read_to_string(
+        log_dir.join("schemata_cargo-mutants-testdata-small-well-tested.log"),
+    )
+    .unwrap();
+    assert!(schemata_log
+        .contains("build 4 mutants of cargo-mutants-testdata-small-well-tested together"));
+    // The build ran once, for all of them.
+    assert_eq!(schemata_log.matches("cargo test --no-run").count(), 1);
+}
+
+#[test]
+fn mutants_of_struct_with_no_default_build_together() {
+    // The struct is defined in the tree without `Default`, so there's no unviable
+    // mutant and all the mutants build in one schemata build.
+    let tmp_src_dir = copy_of_testdata("struct_with_no_default");
+    run()
+        .args([
+            "mutants",
+            "--schemata",
+            "--genre=Literal",
+            "--no-shuffle",
+            "--no-times",
+            "--unviable",
+        ])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unviable").not());
+    assert_eq!(
+        outcome_json_counts(&tmp_src_dir),
+        serde_json::json!({
+            "success": 0,
+            "caught": 4,
+            "unviable": 0,
+            "missed": 0,
+            "timeout": 0,
+            "total_mutants": 4,
+        })
+    );
+}
+
+#[test]
+fn unviable_mutants_fall_back_to_separate_builds() {
+    let tmp_src_dir = copy_of_testdata("struct_from_macro");
+    run()
+        .args([
+            "mutants",
+            "--schemata",
+            "--genre=Literal",
+            "--no-shuffle",
+            "--no-times",
+            "--unviable",
+        ])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"unviable *src/lib.rs:\d+:\d+: replace make_an_s -> S with Default::default\(\)",
+            )
+            .unwrap(),
+        );
+    assert_eq!(
+        outcome_json_counts(&tmp_src_dir),
+        serde_json::json!({
+            "success": 0,
+            "caught": 4,
+            "unviable": 1,
+            "missed": 0,
+            "timeout": 0,
+            "total_mutants": 5,
+        })
+    );
+}
+
+#[test]
+fn mutants_built_together_are_tested_in_parallel() {
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    run()
+        .args(["mutants", "--schemata", "--jobs=2", "--no-times"])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .success();
+    assert_eq!(
+        outcome_json_counts(&tmp_src_dir),
+        serde_json::json!({
+            "success": 0,
+            "caught": 4,
+            "unviable": 0,
+            "missed": 0,
+            "timeout": 0,
+            "total_mutants": 4,
+        })
+    );
+    let log_dir = tmp_src_dir.path().join("mutants.out/log");
+    let schemata_log = std::fs::read_to_string(
+        log_dir.join("schemata_cargo-mutants-testdata-small-well-tested.log"),
+    )
+    .unwrap();
+    assert_eq!(schemata_log.matches("cargo test --no-run").count(), 1);
+    // The copy of the built tree is reused, rather than being built again.
+    for entry in std::fs::read_dir(&log_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.to_string_lossy().contains("src__") {
+            let log = std::fs::read_to_string(&path).unwrap();
+            assert!(!log.contains("Compiling"), "{path:?} was rebuilt:\n{log}");
+        }
+    }
+}
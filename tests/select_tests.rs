@@ -0,0 +1,107 @@
+// Copyright 2024 Martin Pool
+
+//! Tests for `--select-tests`, running only the tests that cover each mutant.
+//!
+//! These need the `llvm-tools-preview` rustup component, and are skipped if it's
+//! not installed.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+
+mod util;
+use util::{copy_of_testdata, outcome_json, run};
+
+/// True if `llvm-profdata` is installed in the sysroot of the toolchain running the tests.
+fn llvm_tools_installed() -> bool {
+    let Ok(output) = Command::new("rustc").args(["--print", "sysroot"]).output() else {
+        return false;
+    };
+    let sysroot = String::from_utf8_lossy(&output.stdout);
+    let Ok(targets) = Path::new(sysroot.trim()).join("lib/rustlib").read_dir() else {
+        return false;
+    };
+    targets.flatten().any(|target| {
+        target
+            .path()
+            .join("bin")
+            .join(format!("llvm-profdata{}", std::env::consts::EXE_SUFFIX))
+            .is_file()
+    })
+}
+
+#[test]
+fn only_covering_tests_are_run_and_untested_mutants_are_missed() {
+    if !llvm_tools_installed() {
+        eprintln!("skipped: llvm-tools-preview is not installed");
+        return;
+    }
+    let tmp_src_dir = copy_of_testdata("small_well_tested");
+    let lib_path = tmp_src_dir.path().join("src/lib.rs");
+    let mut code = fs::read_to_string(&lib_path).unwrap();
+    code.push_str("\npub fn double(n: u32) -> u32 {\n    n * 2\n}\n");
+    fs::write(&lib_path, code).unwrap();
+    run()
+        .args([
+            "mutants",
+            "--select-tests",
+            "--no-shuffle",
+            "--no-times",
+            "--caught",
+        ])
+        .current_dir(tmp_src_dir.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Measuring coverage of 1 tests"))
+        .stdout(predicate::function(|stdout: &str| {
+            insta::assert_snapshot!(stdout, @r###"
+            Found 8 mutants to test
+            ok       Unmutated baseline
+            MISSED   src/lib.rs:24:5: replace double -> u32 with 0
+            MISSED   src/lib.rs:24:5: replace double -> u32 with 1
+            MISSED   src/lib.rs:24:7: replace * with + in double
+            MISSED   src/lib.rs:24:7: replace * with / in double
+            caught   src/lib.rs:5:5: replace factorial -> u32 with 0
+            caught   src/lib.rs:5:5: replace factorial -> u32 with 1
+            caught   src/lib.rs:7:11: replace *= with += in factorial
+            caught   src/lib.rs:7:11: replace *= with /= in factorial
+            8 mutants tested: 4 missed, 4 caught
+            "###);
+            true
+        }));
+
+    let outcomes = outcome_json(&tmp_src_dir)["outcomes"].clone();
+    let argvs = |function: &str| {
+        outcomes
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|outcome| {
+                outcome["scenario"]["Mutant"]["function"]["function_name"] == function
+            })
+            .map(|outcome| {
+                outcome["phase_results"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|pr| pr["argv"].as_array().unwrap().len())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    // Untested mutants are not built, and no tests are run.
+    assert_eq!(argvs("double"), vec![vec![0]; 4]);
+    assert_eq!(argvs("factorial").len(), 4);
+
+    let log = fs::read_to_string(
+        tmp_src_dir
+            .path()
+            .join("mutants.out/log/src__lib.rs_line_7_col_11.log"),
+    )
+    .unwrap();
+    assert!(log.contains("running only the tests that execute this code: test::test_factorial"));
+    assert!(log.contains("-- --exact test::test_factorial"));
+}